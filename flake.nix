@@ -62,6 +62,19 @@
 
           hostPackages = lib.foldr (guest: accum: accum // (createPackages guest)) { } guests;
 
+          # Run the guest program directly, without a zkVM
+          nativePackages = lib.foldr (
+            guest: accum:
+            accum
+            // {
+              "native/${guest}" = callPackage ./native/default.nix {
+                inherit guest;
+                inherit craneLib-default;
+                zkvmLib = (import ./zkvmLib.nix) pkgs guest;
+              };
+            }
+          ) { } guests;
+
           guestPackages = lib.foldr (
             guest: accum:
             accum
@@ -70,6 +83,7 @@
                 inherit guest;
                 inherit zkvms;
                 inherit hostPackages;
+                nativePackage = nativePackages."native/${guest}";
                 inherit craneLib-default;
                 rev = inputs.self.rev or inputs.self.dirtyRev;
               };
@@ -90,6 +104,7 @@
 
           packages =
             hostPackages
+            // nativePackages
             // guestPackages
             // {
               rust-format-all = callPackage ./rust-format-all.nix { };
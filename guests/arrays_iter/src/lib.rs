@@ -6,7 +6,11 @@ extern crate alloc;
 use alloc::vec::Vec;
 
 #[guests_macro::proving_entrypoint]
-pub fn main(numbers: Vec<i32>, remainder: i32, divisor: i32) -> bool {
+pub fn main(
+    #[public] numbers: Vec<i32>,
+    #[public] remainder: i32,
+    #[private] divisor: i32,
+) -> bool {
     for n in numbers {
         if n % divisor != remainder {
             return false;
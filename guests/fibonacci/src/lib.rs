@@ -1,7 +1,7 @@
 #![cfg_attr(feature = "no_std", no_std)]
 
 #[guests_macro::proving_entrypoint]
-pub fn main(n: u8, fN: u64) -> bool {
+pub fn main(#[public] n: u8, #[private] fN: u64) -> bool {
     let mut f0 = 0;
     let mut f1 = 1;
 
@@ -3,7 +3,7 @@
 use nalgebra::Matrix2;
 
 #[guests_macro::proving_entrypoint]
-pub fn main(n: u8, fN: u64) -> bool {
+pub fn main(#[public] n: u8, #[private] fN: u64) -> bool {
     let r = Matrix2::new(1, 1, 1, 0).pow((n - 1).into())[(0, 0)];
 
     r == fN
@@ -3,10 +3,14 @@
 #[cfg(feature = "no_std")]
 extern crate alloc;
 #[cfg(feature = "no_std")]
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 
 #[guests_macro::proving_entrypoint]
-pub fn main(graph: Vec<Vec<bool>>, colors: u32, coloring: Vec<[u32; 2]>) -> bool {
+pub fn main(
+    #[public] graph: Vec<Vec<bool>>,
+    #[public] colors: u32,
+    #[private] coloring: Vec<[u32; 2]>,
+) -> bool {
     // Does it use the correct amount of colors?
     let mut max_color = coloring[0][1];
     for nc in &coloring {
@@ -27,3 +31,37 @@ pub fn main(graph: Vec<Vec<bool>>, colors: u32, coloring: Vec<[u32; 2]>) -> bool
 
     ret
 }
+
+#[path = "../../../guests_macro/src/split_mix64.rs"]
+mod split_mix64;
+use split_mix64::SplitMix64;
+
+/// Creates a graph of `size` vertices and a valid coloring with (up to)
+/// three colors.
+///
+/// Vertices are colored first and then every pair of differently colored
+/// vertices is connected with a probability of one half.
+#[guests_macro::input_generator]
+pub fn generate(size: usize, seed: u64) -> (Vec<Vec<bool>>, u32, Vec<[u32; 2]>) {
+    let colors = size.min(3) as u32;
+    let mut rng = SplitMix64(seed);
+
+    // The first vertices get every color, so all colors are used
+    let coloring = (0..size as u32)
+        .map(|vertex| match vertex < colors {
+            true => [vertex, vertex],
+            false => [vertex, (rng.next() % colors as u64) as u32],
+        })
+        .collect::<Vec<[u32; 2]>>();
+
+    let mut graph = vec![vec![false; size]; size];
+    for i in 0..size {
+        for j in i + 1..size {
+            let edge = coloring[i][1] != coloring[j][1] && rng.next() % 2 == 0;
+            graph[i][j] = edge;
+            graph[j][i] = edge;
+        }
+    }
+
+    (graph, colors, coloring)
+}
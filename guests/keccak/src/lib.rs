@@ -15,7 +15,7 @@ use sha3_risc0::{Digest, Keccak256};
 use sha3::{Digest, Keccak256};
 
 #[guests_macro::proving_entrypoint]
-pub fn main(secret: Vec<u8>, hash: Vec<u8>) -> bool {
+pub fn main(#[private] secret: Vec<u8>, #[public] hash: Vec<u8>) -> bool {
     #[cfg(feature = "zkm")]
     let result = zkm_runtime::io::keccak(&secret.as_slice());
 
@@ -30,3 +30,19 @@ pub fn main(secret: Vec<u8>, hash: Vec<u8>) -> bool {
 
     output.to_vec() == hash
 }
+
+#[path = "../../../guests_macro/src/split_mix64.rs"]
+mod split_mix64;
+use split_mix64::SplitMix64;
+
+/// Creates a secret of `size` bytes and its hash
+#[guests_macro::input_generator]
+pub fn generate(size: usize, seed: u64) -> (Vec<u8>, Vec<u8>) {
+    let mut rng = SplitMix64(seed);
+    let secret = (0..size).map(|_| rng.next() as u8).collect::<Vec<u8>>();
+
+    // Always hashed on the host, so the zkVM's accelerated Keccak isn't used
+    let hash = <sha3::Keccak256 as sha3::Digest>::digest(&secret).to_vec();
+
+    (secret, hash)
+}
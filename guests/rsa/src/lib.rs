@@ -8,16 +8,32 @@ use alloc::vec::Vec;
 use sha2::{Digest, Sha256};
 
 #[cfg(feature = "sp1")]
-use rsa_sp1::{pkcs8::DecodePublicKey, Pkcs1v15Sign, RsaPublicKey};
+use rsa_sp1::{
+    pkcs8::{DecodePublicKey, EncodePublicKey},
+    rand_core::{CryptoRng, Error, RngCore},
+    Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey,
+};
 
 #[cfg(feature = "risc0")]
-use rsa_risc0::{pkcs8::DecodePublicKey, Pkcs1v15Sign, RsaPublicKey};
+use rsa_risc0::{
+    pkcs8::{DecodePublicKey, EncodePublicKey},
+    rand_core::{CryptoRng, Error, RngCore},
+    Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey,
+};
 
 #[cfg(not(any(feature = "sp1", feature = "risc0")))]
-use rsa::{pkcs8::DecodePublicKey, Pkcs1v15Sign, RsaPublicKey};
+use rsa::{
+    pkcs8::{DecodePublicKey, EncodePublicKey},
+    rand_core::{CryptoRng, Error, RngCore},
+    Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey,
+};
 
 #[guests_macro::proving_entrypoint]
-pub fn main(public_key: Vec<u8>, message: String, signature: Vec<u8>) -> bool {
+pub fn main(
+    #[public] public_key: Vec<u8>,
+    #[public] message: String,
+    #[public] signature: Vec<u8>,
+) -> bool {
     let public_key = RsaPublicKey::from_public_key_der(&public_key).unwrap();
 
     let mut hasher = Sha256::new();
@@ -28,3 +44,56 @@ pub fn main(public_key: Vec<u8>, message: String, signature: Vec<u8>) -> bool {
         .verify(Pkcs1v15Sign::new::<Sha256>(), &hashed_msg, &signature)
         .is_ok()
 }
+
+#[path = "../../../guests_macro/src/split_mix64.rs"]
+mod split_mix64;
+use split_mix64::SplitMix64;
+
+impl RngCore for SplitMix64 {
+    fn next_u32(&mut self) -> u32 {
+        self.next() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.next()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+// Required for key generation, the keys are only used for benchmarking
+impl CryptoRng for SplitMix64 {}
+
+/// Creates a 2048-bit key and a message of `size` lowercase letters, signed
+/// with the key
+#[guests_macro::input_generator]
+pub fn generate(size: usize, seed: u64) -> (Vec<u8>, String, Vec<u8>) {
+    let mut rng = SplitMix64(seed);
+    let message = (0..size)
+        .map(|_| (b'a' + (rng.next_u32() % 26) as u8) as char)
+        .collect::<String>();
+
+    let private_key = RsaPrivateKey::new(&mut rng, 2048).unwrap();
+    let public_key = private_key
+        .to_public_key()
+        .to_public_key_der()
+        .unwrap()
+        .into_vec();
+
+    let hashed_msg = Sha256::digest(&message);
+    let signature = private_key
+        .sign(Pkcs1v15Sign::new::<Sha256>(), &hashed_msg)
+        .unwrap();
+
+    (public_key, message, signature)
+}
@@ -15,7 +15,7 @@ use sha2_risc0::{Digest, Sha256};
 use sha2::{Digest, Sha256};
 
 #[guests_macro::proving_entrypoint]
-pub fn main(secret: Vec<u8>, hash: Vec<u8>) -> bool {
+pub fn main(#[private] secret: Vec<u8>, #[public] hash: Vec<u8>) -> bool {
     let mut hasher = Sha256::new();
     hasher.update(secret);
     let result = hasher.finalize();
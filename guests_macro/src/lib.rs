@@ -1,7 +1,15 @@
-use proc_macro::TokenStream;
+use proc_macro::{Delimiter, Group, TokenStream, TokenTree};
 use std::{fs::File, io::Write};
 mod parse_fn;
 
+/// Path of the guest's generator.txt, next to its Cargo.toml
+fn generator_note() -> String {
+    format!(
+        "{}/generator.txt",
+        std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is missing")
+    )
+}
+
 /// Create an `entrypoint_expr` macro inside the guest program. This will be
 /// used inside the ZKVM guest program to call the ZKVM guest wrapper's
 /// `make_wrapper` macro with entrypoint function type.
@@ -20,30 +28,40 @@ mod parse_fn;
 /// Inside your guest (under guests directory) add an attribute above your main
 /// (entrypoint/start) function. It takes no arguments.
 ///
+/// Every argument should be annotated with `#[public]` or `#[private]`, which
+/// decides whether its value is part of the proof's public output. Without
+/// annotations, the public arguments are the keys of the guest's
+/// `default_public_input.toml`.
+///
 /// ```rust
 /// #[guests_macro::proving_entrypoint]
-/// fn main(...) -> ... { ..... }
+/// fn main(#[public] ...: ..., #[private] ...: ..., ...) -> ... { ..... }
 /// ```
 ///
 /// # Example output
 ///
 /// ```rust
+/// fn main(...: ..., ...: ..., ...) -> ... { ..... }
+///
 /// #[macro_export]
 /// macro_rules! entrypoint_expr {
 ///     () => {
-///         make_wrapper!{fn main(...) -> ...}
+///         make_wrapper!{fn main(#[public] ...: ..., #[private] ...: ..., ...) -> ...}
 ///     };
 /// }
 /// ```
 #[proc_macro_attribute]
-pub fn proving_entrypoint(_: TokenStream, mut item: TokenStream) -> TokenStream {
-    let fd = parse_fn::FunctionDefinition::new(&item);
+pub fn proving_entrypoint(_: TokenStream, item: TokenStream) -> TokenStream {
+    let fd = parse_fn::FunctionDefinition::new(&without_attributes(&item));
+    // The annotations are only understood by this macro, however they're kept
+    // in the function's type, so wrappers and hosts know the public arguments
+    let mut item = without_annotations(&item);
     let fn_type = format!("fn {}{} -> {}", fd.name, fd.args, fd.return_type).replace('\n', " ");
 
     // We also need to pass some type information to the host program compile-time.
     // Put it in the file guests/type.txt.
     let mut output = File::create("../type.txt").unwrap();
-    write!(output, "{fn_type}");
+    write!(output, "{fn_type}").unwrap();
 
     item.extend(
         format!(
@@ -59,3 +77,102 @@ pub fn proving_entrypoint(_: TokenStream, mut item: TokenStream) -> TokenStream
     );
     item
 }
+
+/// Note the input generator of the guest program, so hosts can create inputs
+/// of a given size with `--generate size=N --seed S`.
+///
+/// The generator takes the size of the input and a seed and returns the
+/// arguments of the entrypoint function, in the same order. Its output
+/// should only depend on the size and seed. Like the entrypoint's type, its
+/// type is noted in a (generated and ignored by git) file, generator.txt in
+/// the guest's directory. Guests without a generator don't have it, so
+/// generator.txt has to be deleted alongside a removed generator.
+///
+/// # Usage
+///
+/// Inside your guest (under guests directory) add an attribute above the
+/// (public) generator function. It takes no arguments.
+///
+/// ```rust
+/// #[guests_macro::input_generator]
+/// pub fn generate(size: usize, seed: u64) -> (..., ...) { ..... }
+/// ```
+#[proc_macro_attribute]
+pub fn input_generator(_: TokenStream, item: TokenStream) -> TokenStream {
+    let fd = parse_fn::FunctionDefinition::new(&without_attributes(&item));
+    if fd.types().len() != 2 {
+        panic!("An input generator takes exactly two arguments: the size and the seed!");
+    }
+    let fn_type = format!("fn {}{} -> {}", fd.name, fd.args, fd.return_type).replace('\n', " ");
+
+    let mut output = File::create(generator_note()).unwrap();
+    write!(output, "{fn_type}").unwrap();
+
+    item
+}
+
+/// Removes the outer attributes (like doc comments) of an item, which are
+/// passed to attribute macros alongside the item.
+///
+/// **Input:**  "#[...] pub fn name(...) -> ... { ..... }"
+/// **Output:** "pub fn name(...) -> ... { ..... }"
+fn without_attributes(item: &TokenStream) -> TokenStream {
+    let mut tokens = item.clone().into_iter();
+    let mut out = TokenStream::new();
+    while let Some(tt) = tokens.next() {
+        match tt {
+            // Skip the bracketed group after the #
+            TokenTree::Punct(ref punct) if punct.as_char() == '#' => {
+                tokens.next();
+            }
+            _ => out.extend([tt].into_iter()),
+        }
+    }
+    out
+}
+
+/// Removes the `#[public]` and `#[private]` annotations from the arguments of
+/// a function.
+///
+/// **Input:**  "fn name(#[public] p1: t1, #[private] p2: t2, ...) -> ... { ..... }"
+/// **Output:** "fn name(p1: t1, p2: t2, ...) -> ... { ..... }"
+fn without_annotations(item: &TokenStream) -> TokenStream {
+    let mut out = TokenStream::new();
+    let mut in_args = false;
+    for tt in item.clone() {
+        match tt {
+            TokenTree::Ident(ref ident) if ident.to_string() == "fn" => in_args = true,
+            // The first parenthesized group after "fn" holds the arguments
+            TokenTree::Group(ref group)
+                if in_args && group.delimiter() == Delimiter::Parenthesis =>
+            {
+                in_args = false;
+
+                let mut args = TokenStream::new();
+                let mut tokens = group.stream().into_iter().peekable();
+                while let Some(tt) = tokens.next() {
+                    if let TokenTree::Punct(ref punct) = tt {
+                        let annotation = match tokens.peek() {
+                            Some(TokenTree::Group(attribute)) => ["public", "private"]
+                                .contains(&attribute.stream().to_string().as_str()),
+                            _ => false,
+                        };
+                        if punct.as_char() == '#' && annotation {
+                            tokens.next();
+                            continue;
+                        }
+                    }
+                    args.extend([tt].into_iter());
+                }
+
+                let mut args = Group::new(Delimiter::Parenthesis, args);
+                args.set_span(group.span());
+                out.extend([TokenTree::Group(args)].into_iter());
+                continue;
+            }
+            _ => {}
+        }
+        out.extend([tt].into_iter());
+    }
+    out
+}
@@ -23,17 +23,40 @@ pub struct FunctionDefinition {
 }
 
 impl FunctionDefinition {
+    /// Parses a function definition, whose arguments may be annotated with
+    /// `#[public]` or `#[private]`.
+    ///
+    /// When annotated, every argument must be and the annotations decide
+    /// which arguments are public. Otherwise (in legacy guests) the public
+    /// arguments are the keys of the guest's `default_public_input.toml`.
     pub fn new(item: &TokenStream) -> FunctionDefinition {
         let (name, args, return_type) = Self::split_fn(item);
-        let (patterns, types) = Self::args_divide(&args);
+        let (patterns, types, annotations) = Self::args_divide(&args);
 
-        let public_inputs = toml::from_str::<toml::Table>(include_str!(concat!(
-            env!("INPUTS_DIR"),
-            "/default_public_input.toml"
-        )))
-        .unwrap();
+        let public = if annotations.iter().any(Option::is_some) {
+            if let Some((pattern, _)) = patterns
+                .iter()
+                .zip(&annotations)
+                .find(|(_, annotation)| annotation.is_none())
+            {
+                panic!("Argument \"{pattern}\" of \"{name}\" has no #[public] or #[private] annotation, while others do!");
+            }
+            patterns
+                .iter()
+                .zip(&annotations)
+                .filter(|(_, annotation)| **annotation == Some(true))
+                .map(|(pattern, _)| pattern.to_string())
+                .collect::<Vec<String>>()
+        } else {
+            let public_inputs = toml::from_str::<toml::Table>(include_str!(concat!(
+                env!("INPUTS_DIR"),
+                "/default_public_input.toml"
+            )))
+            .unwrap();
+            public_inputs.keys().cloned().collect::<Vec<String>>()
+        };
         let ((public_patterns, public_types), (private_patterns, private_types)) =
-            Self::args_divide_public(&patterns, &types, &public_inputs.keys().collect());
+            Self::args_divide_public(&patterns, &types, &public.iter().collect());
 
         FunctionDefinition {
             name,
@@ -98,6 +121,17 @@ impl FunctionDefinition {
         Self::combine(self.private_patterns.clone(), self.private_types.clone())
     }
 
+    /// Arguments group without `#[public]` and `#[private]` annotations,
+    /// unlike `args`
+    ///
+    /// **Output:** "(p1 : t1, p2 : t2, ...)"
+    pub fn grouped_arguments(&self) -> TokenStream {
+        TokenStream::from(TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            Self::group_stream(&self.arguments()),
+        )))
+    }
+
     /// Split function definition into triplet of name, arguments and output types.
     ///
     /// **Input:**  "fn name(...) -> ... { ..... }"
@@ -154,12 +188,13 @@ impl FunctionDefinition {
         (name, args, ret)
     }
 
-    /// Split arguments group into two vectors: one for all argument names and one
-    /// for every argument type.
+    /// Split arguments group into three vectors: one for all argument names, one
+    /// for every argument type and one for every argument's annotation
+    /// (`Some(true)` for `#[public]`, `Some(false)` for `#[private]`).
     ///
-    /// **Input:**  "(p1 : t1, p2: t2, ...)"
-    /// **Output:** vec!["p1", "p2", ...], vec!["t1", "t2", ...]
-    fn args_divide(item: &TokenStream) -> (Vec<TokenStream>, Vec<TokenStream>) {
+    /// **Input:**  "(#[public] p1 : t1, p2: t2, ...)"
+    /// **Output:** vec!["p1", "p2", ...], vec!["t1", "t2", ...], vec![Some(true), None, ...]
+    fn args_divide(item: &TokenStream) -> (Vec<TokenStream>, Vec<TokenStream>, Vec<Option<bool>>) {
         let contents;
         if let TokenTree::Group(group) = item.clone().into_iter().next().unwrap() {
             contents = group.stream().into_iter();
@@ -169,12 +204,29 @@ impl FunctionDefinition {
 
         let mut patterns = Vec::new();
         let mut types = Vec::new();
+        let mut annotations = Vec::new();
+        let mut annotation = None;
         let mut ts = TokenStream::new();
         let mut ignore_next = false;
         let mut angle_level = 0;
+        let mut in_attribute = false;
 
         for tt in contents {
             match tt {
+                // Attributes are only allowed before patterns
+                TokenTree::Punct(ref punct) if punct.as_char() == '#' && ts.is_empty() => {
+                    in_attribute = true;
+                    continue;
+                }
+                TokenTree::Group(ref group) if in_attribute => {
+                    in_attribute = false;
+                    match group.stream().to_string().as_str() {
+                        "public" => annotation = Some(true),
+                        "private" => annotation = Some(false),
+                        _ => {}
+                    }
+                    continue;
+                }
                 TokenTree::Punct(ref punct) => {
                     // Ignore "::"
                     if punct.spacing() == Spacing::Joint && punct.as_char() == ':' {
@@ -188,6 +240,7 @@ impl FunctionDefinition {
                             '>' => angle_level -= 1,
                             ':' => {
                                 patterns.push(ts);
+                                annotations.push(annotation.take());
                                 ts = TokenStream::new();
                                 continue;
                             }
@@ -210,8 +263,11 @@ impl FunctionDefinition {
             ts.extend([tt].into_iter());
         }
 
-        types.push(ts);
-        (patterns, types)
+        // A trailing comma leaves nothing after it
+        if !ts.is_empty() {
+            types.push(ts);
+        }
+        (patterns, types, annotations)
     }
 
     /// Like `args_divide`, however two tuples of vectors are returned: the first
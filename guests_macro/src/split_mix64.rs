@@ -0,0 +1,24 @@
+//! Pseudo-random numbers for the input generators of guests. Inputs only need
+//! to be reproducible, not unpredictable, so it isn't cryptographically
+//! secure.
+//!
+//! It isn't part of the guests_macro crate, since procedural macro crates
+//! can't export anything else. Instead, guests include it via a [mod path
+//! attribute](https://doc.rust-lang.org/reference/items/modules.html#the-path-attribute):
+//!
+//! ```rust
+//! #[path = "../../../guests_macro/src/split_mix64.rs"]
+//! mod split_mix64;
+//! ```
+
+pub struct SplitMix64(pub u64);
+
+impl SplitMix64 {
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
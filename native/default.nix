@@ -0,0 +1,73 @@
+{ zkvmLib, stdenv, lib, craneLib-default, guest, }:
+let
+  pname = "native_${guest}";
+
+  cargoLockDrv = stdenv.mkDerivation {
+    name = "CargoLocks-native";
+    src = lib.fileset.toSource {
+      root = ./..;
+      fileset = ./..;
+    };
+
+    installPhase = ''
+      mkdir -p "$out"
+
+      cat ./native/host/Cargo.lock > lockfile
+      tail -n +4 ./guests/${guest}/Cargo.lock >> lockfile
+      echo >> lockfile
+
+      awk '${zkvmLib.removeDuplicates}' lockfile > "$out/Cargo.lock"
+    '';
+  };
+in craneLib-default.buildPackage {
+  inherit pname;
+  version = "0.1.0";
+
+  src = with lib.fileset;
+    toSource {
+      root = ./..;
+      fileset = intersection (gitTracked ./..)
+        (unions [ ./. ../guests ../guests_macro ../zkvms_host_io ]);
+    };
+
+  cargoVendorDir = craneLib-default.vendorCargoDeps { src = cargoLockDrv; };
+  cargoArtifacts = null;
+
+  postPatch = ''
+    pushd native/host
+
+    cp '${cargoLockDrv}/Cargo.lock' Cargo.lock
+    cargo add --path "../../guests/${guest}" --rename zkp --offline
+
+    popd
+  '';
+
+  buildPhase = ''
+    export INPUTS_DIR="$PWD/guests/${guest}"
+    export ZKVM="native" ZKVM_REV="$(rustc --version | cut -d ' ' -f 2)" GUEST="${guest}"
+
+    # Compiling the guest creates guests/type.txt, which zkvms_host_io needs
+    pushd guests/${guest}
+    cargo check --release --offline
+    popd
+
+    pushd native/host
+    runHook preBuild
+
+    cargo build --release --offline
+
+    runHook postBuild
+    popd
+  '';
+
+  installPhase = ''
+    runHook preInstall
+
+    mkdir -p "$out"/bin
+    mv native/host/target/release/host-native "$out"/bin/${pname}
+
+    runHook postInstall
+  '';
+
+  doCheck = false;
+}
@@ -0,0 +1,49 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use wrapper_macro::make_wrapper;
+use zkvms_host_io::{
+    benchmarkable, input_generator, read_args, time_phase, Capabilities, Configurable, HostError,
+    Input, Knob, Phase, Return, RunType,
+    RunType::{Execute, Native, Prove, Verify},
+    RunWith,
+};
+
+/// Declares the capabilities of the native host
+struct NativeHost;
+
+impl Capabilities for NativeHost {
+    const RUN_TYPES: &'static [RunType] = &[Native];
+    const PROOF_MODES: &'static [&'static str] = &[];
+    const CYCLE_COUNTING: &'static [RunType] = &[];
+    const OUTPUT_CHECKING: &'static [RunType] = &[Native];
+    const PROOF_EXPORT: bool = false;
+    const PROOF_IMPORT: bool = false;
+}
+
+impl Configurable for NativeHost {
+    const KNOBS: &'static [Knob] = &[];
+}
+
+fn main() {
+    let run_info = read_args::<NativeHost>(input_generator!());
+    match run(&run_info) {
+        Ok(()) => run_info.succeed(),
+        Err(error) => run_info.fail(error),
+    }
+}
+
+fn run(run_info: &RunWith) -> Result<(), HostError> {
+    // Calls the guest's entrypoint function, created by (native) wrapper_macro
+    let run_guest = zkp::entrypoint_expr!();
+
+    match run_info.run_type {
+        Native => benchmarkable! {
+            // The panic message is already printed by the default panic hook
+            let output = time_phase(Phase::Execute, || catch_unwind(AssertUnwindSafe(|| run_guest(run_info.input.clone()))))
+                .map_err(|_| HostError::GuestPanic("the guest program panicked".to_string()))?;
+            println!("Output: {:?}", output);
+            run_info.check_return(&output)?;
+        },
+        Execute | Prove | Verify => unreachable!(),
+    }
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use proc_macro::TokenStream;
+
+#[path = "../../../guests_macro/src/parse_fn.rs"]
+mod parse_fn;
+use crate::parse_fn::FunctionDefinition;
+
+/// Create a closure, which converts an `Input` into the entrypoint function's
+/// arguments and calls the guest entrypoint function with them.
+///
+/// # Usage
+///
+/// Inside the native host (excluding the `entrypoint_expr` call):
+///
+/// ```rust
+/// make_wrapper!{fn main(...) -> ...}
+/// ```
+///
+/// # Example output
+///
+/// ```rust
+/// |input: Input| -> Return {
+///     let (..., ..., ...): (..., ..., ...) = input.into();
+///     zkp::main(..., ..., ...)
+/// }
+/// ```
+#[proc_macro]
+pub fn make_wrapper(item: TokenStream) -> TokenStream {
+    let fd = FunctionDefinition::new(&item);
+
+    format!(
+        "|input: Input| -> Return {{
+            let ({}): ({}) = input.into();
+            zkp::{}({})
+        }}",
+        fd.grouped_patterns(),
+        fd.grouped_types(),
+        fd.name,
+        fd.grouped_patterns()
+    )
+    .parse::<TokenStream>()
+    .unwrap()
+}
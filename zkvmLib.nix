@@ -1,5 +1,62 @@
 pkgs: guest:
 let
+  # Since we're concatenating Cargo.lock files, duplicate package entries
+  # are inevitable and cargo crashes when it encounters them.
+  # We'll manually remove all duplicates and cargo will be happy.
+  # This is a disgusting hack, but it's the best I've come up with.
+  removeDuplicates = /* awk */ ''
+    BEGIN {
+        unique = 1
+    }
+
+    /^\[\[package\]\]/ { unique = 0; next }
+
+    /^name = / {
+        match($0, /".*"/)
+        name = substr($0, RSTART + 1, RLENGTH - 2)
+        next
+    }
+
+    name && /^version = / {
+        match($0, /".*"/)
+        version = substr($0, RSTART + 1, RLENGTH - 2)
+        next
+    }
+
+    version && /^source = / {
+        match($0, /".*"/)
+        source = substr($0, RSTART + 1, RLENGTH - 2)
+        next
+    }
+
+    source && /^checksum = / {
+        match($0, /".*"/)
+        checksum = substr($0, RSTART + 1, RLENGTH - 2)
+        next
+    }
+
+    name && !unique {
+        unique = (index(versions[name], version) == 0) ||
+                 (source && index(sources[name], source) == 0) ||
+                 (checksum && index(checksums[name], checksum) == 0)
+
+        if (unique) {
+            versions[name]  = versions[name] version
+            sources[name]   = sources[name] source
+            checksums[name] = checksums[name] checksum
+
+            print "[[package]]"
+            print "name = \"" name "\""
+            print "version = \"" version "\""
+            if (source)   print "source = \"" source "\""
+            if (checksum) print "checksum = \"" checksum "\""
+        }
+        name = ""; version = ""; source = ""; checksum = ""
+    }
+
+    unique || /^$/ { print }
+  '';
+
   generateCargoLocks = craneLib: args: rec {
     cargoLockDrv = pkgs.stdenv.mkDerivation {
       name = "CargoLocks-${args.pname}";
@@ -8,64 +65,7 @@ let
         fileset = ./.;
       };
 
-      installPhase = let
-        # Since we're concatenating Cargo.lock files, duplicate package entries
-        # are inevitable and cargo crashes when it encounters them.
-        # We'll manually remove all duplicates and cargo will be happy.
-        # This is a disgusting hack, but it's the best I've come up with.
-        removeDuplicates = /* awk */ ''
-          BEGIN {
-              unique = 1
-          }
-
-          /^\[\[package\]\]/ { unique = 0; next }
-
-          /^name = / {
-              match($0, /".*"/)
-              name = substr($0, RSTART + 1, RLENGTH - 2)
-              next
-          }
-
-          name && /^version = / {
-              match($0, /".*"/)
-              version = substr($0, RSTART + 1, RLENGTH - 2)
-              next
-          }
-
-          version && /^source = / {
-              match($0, /".*"/)
-              source = substr($0, RSTART + 1, RLENGTH - 2)
-              next
-          }
-
-          source && /^checksum = / {
-              match($0, /".*"/)
-              checksum = substr($0, RSTART + 1, RLENGTH - 2)
-              next
-          }
-
-          name && !unique {
-              unique = (index(versions[name], version) == 0) ||
-                       (source && index(sources[name], source) == 0) ||
-                       (checksum && index(checksums[name], checksum) == 0)
-
-              if (unique) {
-                  versions[name]  = versions[name] version
-                  sources[name]   = sources[name] source
-                  checksums[name] = checksums[name] checksum
-
-                  print "[[package]]"
-                  print "name = \"" name "\""
-                  print "version = \"" version "\""
-                  if (source)   print "source = \"" source "\""
-                  if (checksum) print "checksum = \"" checksum "\""
-              }
-              name = ""; version = ""; source = ""; checksum = ""
-          }
-
-          unique || /^$/ { print }
-        '';
-      in ''
+      installPhase = ''
         mkdir -p "$out"
         cd zkvms/${args.pname}
 
@@ -96,6 +96,8 @@ let
 
   };
 in {
+  inherit removeDuplicates;
+
   buildDepsOnly = craneLib: args:
     if builtins.pathExists ./guests/${guest}/.no_${args.pname} then
       { }
@@ -172,11 +174,16 @@ in {
           echo '${appended}' >> Cargo.toml
 
           popd
+
+          # The host calls the guest's input generator (if it has one) directly
+          pushd zkvms/${args.pname}/host
+          cargo add --path "../../../guests/${guest}" --rename zkp --offline
+          popd
         '';
 
         buildGuestPhase = ''
           export INPUTS_DIR="$PWD/guests/${guest}"
-          export ZKVM="${args.pname}" GUEST="${guest}"
+          export ZKVM="${args.pname}" ZKVM_REV="${args.version}" GUEST="${guest}"
           OLD_PATH="$PATH"
 
           ${if args ? guestToolchain then
@@ -211,7 +218,7 @@ in {
 
         buildPhase = ''
           export INPUTS_DIR="$PWD/guests/${guest}"
-          export ZKVM="${args.pname}" GUEST="${guest}"
+          export ZKVM="${args.pname}" ZKVM_REV="${args.version}" GUEST="${guest}"
           OLD_PATH="$PATH"
 
           ${if args ? hostToolchain then
@@ -1,34 +1,139 @@
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use jolt_sdk::JoltHyperKZGProof;
 use zkvms_host_io::{
-    benchmarkable, output_proof_size, read_args,
-    RunType::{Execute, Prove, Verify},
+    benchmarkable, input_generator, output_cycles, output_proof_size, read_args, read_proof,
+    time_phase, write_proof, Capabilities, Configurable, Cycles, HostError, Knob, Phase,
+    ProofSerializer, Return, RunType,
+    RunType::{Execute, Native, Prove, Verify},
+    RunWith,
 };
 
+/// Implements the zkvms_host_io backend hooks for Jolt
+struct Jolt;
+
+impl ProofSerializer for Jolt {
+    type Proof = JoltHyperKZGProof;
+    // Preprocessing is recreated from the guest ELF
+    type VerifyingKey = ();
+
+    const ENCODING: &'static str = "ark-serialize (compressed)";
+
+    fn serialize_proof(proof: &Self::Proof) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        proof
+            .serialize_compressed(&mut bytes)
+            .expect("failed to serialize proof");
+        bytes
+    }
+
+    fn deserialize_proof(bytes: &[u8]) -> Result<Self::Proof, HostError> {
+        JoltHyperKZGProof::deserialize_compressed(bytes)
+            .map_err(|e| HostError::InvalidInput(format!("failed to deserialize proof: {e}")))
+    }
+
+    fn serialize_key(_: &Self::VerifyingKey) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn deserialize_key(_: &[u8]) -> Result<Self::VerifyingKey, HostError> {
+        Ok(())
+    }
+}
+
+impl Capabilities for Jolt {
+    // Jolt cannot execute the guest without proving it
+    const RUN_TYPES: &'static [RunType] = &[Prove, Verify];
+    const PROOF_MODES: &'static [&'static str] = &["hyperkzg"];
+    const CYCLE_COUNTING: &'static [RunType] = &[Prove];
+    const OUTPUT_CHECKING: &'static [RunType] = &[Prove, Verify];
+    const PROOF_EXPORT: bool = true;
+    const PROOF_IMPORT: bool = true;
+}
+
+impl Configurable for Jolt {
+    const KNOBS: &'static [Knob] = &[];
+}
+
+/// Decodes the value, returned by the guest, from the proof's I/O
+fn proof_return(proof: &JoltHyperKZGProof) -> Result<Return, HostError> {
+    jolt_sdk::postcard::from_bytes(&proof.proof.program_io.outputs)
+        .map_err(|e| HostError::UnexpectedOutput(format!("failed to decode the output: {e}")))
+}
+
 pub fn main() {
-    let run_info = read_args();
-    if run_info.run_type == Execute {
-        panic!("Execution is not supported!");
+    let run_info = read_args::<Jolt>(input_generator!());
+    match run(&run_info) {
+        Ok(()) => run_info.succeed(),
+        Err(error) => run_info.fail(error),
     }
+}
 
+fn run(run_info: &RunWith) -> Result<(), HostError> {
     let elf_path = std::env::var("ELF_PATH").expect("ELF PATH is missing");
     // guest_closures are generated by (Jolt's) wrapper_macro
-    let (prove_guest, verify_guest) = guest::guest_closures(elf_path);
+    // They preprocess the guest program once
+    let (prove_guest, verify_guest) = time_phase(Phase::Setup, || guest::guest_closures(elf_path));
 
     match run_info.run_type {
-        Execute => unreachable!(),
-        Prove => benchmarkable! {
-            let (output, proof) = prove_guest(run_info.input.clone().into());
+        Execute | Native => unreachable!(),
+        Prove => {
+            let mut last_proof = None;
+
+            benchmarkable! {
+                let (output, proof) = time_phase(Phase::Prove, || prove_guest(run_info.input.clone().into()));
+
+                output_proof_size::<Jolt>(&proof);
+                output_cycles(Cycles {
+                    cycles: proof.proof.trace_length as u64,
+                    ..Default::default()
+                });
+
+                println!("Prove output: {:?}", output);
+                run_info.check_return(&output)?;
+
+                last_proof = Some((output, proof));
+            }
 
-            output_proof_size(&proof);
+            if let (Some(path), Some((output, proof))) = (&run_info.proof_out, last_proof) {
+                let output = run_info.public_input.with_return(output);
+                write_proof::<Jolt>(path, run_info, Some(&output), &proof, &())?;
+            }
+        }
+        Verify => {
+            let proof = match &run_info.proof_in {
+                Some(path) => {
+                    let proof = read_proof::<Jolt>(path)?.0;
+                    run_info.check_return(&proof_return(&proof)?)?;
+                    proof
+                }
+                None => {
+                    let (output, proof) = prove_guest(run_info.input.clone().into());
+                    run_info.check_return(&output)?;
+                    proof
+                }
+            };
 
-            println!("Prove output: {:?}", output);
-        },
-        Verify => benchmarkable! {
-            let (_, proof) = prove_guest(run_info.input.clone().into());
+            output_proof_size::<Jolt>(&proof);
 
-            output_proof_size(&proof);
+            // JoltHyperKZGProof doesn't derive Clone and verify_guest takes
+            // ownership, so every run verifies its own copy. The copies are
+            // deserialized beforehand, so only verification is timed.
+            let bytes = Jolt::serialize_proof(&proof);
+            let mut proofs = (0..run_info.warmup + run_info.runs)
+                .map(|_| Jolt::deserialize_proof(&bytes))
+                .collect::<Result<Vec<_>, _>>()?;
 
-            let is_valid = verify_guest(proof);
-            println!("Verify is valid: {:?}", is_valid);
-        },
+            benchmarkable! {
+                let proof = proofs.pop().unwrap();
+                let is_valid = time_phase(Phase::Verify, || verify_guest(proof));
+                println!("Verify is valid: {:?}", is_valid);
+                if !is_valid {
+                    return Err(HostError::VerificationFailed(
+                        "the verifier rejected the proof".to_string(),
+                    ));
+                }
+            }
+        }
     }
+    Ok(())
 }
@@ -53,7 +53,9 @@ pub fn make_wrapper(item: TokenStream) -> TokenStream {
     func.extend(
         format!(
             "#[jolt::provable(max_input_size = 100000)] fn guest{} -> {} {{ {} }}",
-            fd.args, fd.return_type, out
+            fd.grouped_arguments(),
+            fd.return_type,
+            out
         )
         .parse::<TokenStream>(),
     );
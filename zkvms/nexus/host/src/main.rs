@@ -1,58 +1,124 @@
-use nexus_sdk::{stwo::seq::Stwo, Local, Prover, Verifiable, Viewable};
+use nexus_sdk::{
+    stwo::seq::{Proof, Stwo},
+    Local, Prover, Verifiable, Viewable,
+};
 use zkvms_host_io::{
-    benchmarkable, output_proof_size, read_args, Input, Return,
-    RunType::{Execute, Prove, Verify},
+    benchmarkable, input_generator, output_proof_size, read_args, time_phase, write_proof,
+    Capabilities, Configurable, HostError, Input, Knob, Phase, ProofSerializer, Return, RunType,
+    RunType::{Execute, Native, Prove, Verify},
+    RunWith,
 };
 
+/// Implements the zkvms_host_io backend hooks for Nexus
+struct Nexus;
+
+impl ProofSerializer for Nexus {
+    type Proof = Proof;
+    type VerifyingKey = ();
+
+    // Nexus uses postcard for all (de)serialization, including guest I/O
+    const ENCODING: &'static str = "postcard";
+
+    fn serialize_proof(proof: &Self::Proof) -> Vec<u8> {
+        postcard::to_allocvec(proof).expect("failed to serialize proof")
+    }
+
+    fn deserialize_proof(bytes: &[u8]) -> Result<Self::Proof, HostError> {
+        postcard::from_bytes(bytes)
+            .map_err(|e| HostError::InvalidInput(format!("failed to deserialize proof: {e}")))
+    }
+
+    fn serialize_key(_: &Self::VerifyingKey) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn deserialize_key(_: &[u8]) -> Result<Self::VerifyingKey, HostError> {
+        Ok(())
+    }
+}
+
+impl Capabilities for Nexus {
+    // Nexus cannot execute the guest without proving it
+    const RUN_TYPES: &'static [RunType] = &[Prove, Verify];
+    const PROOF_MODES: &'static [&'static str] = &["stwo"];
+    const CYCLE_COUNTING: &'static [RunType] = &[];
+    const OUTPUT_CHECKING: &'static [RunType] = &[Prove, Verify];
+    const PROOF_EXPORT: bool = true;
+    // The proof is verified against the execution view, which cannot be
+    // stored alongside it
+    const PROOF_IMPORT: bool = false;
+}
+
+impl Configurable for Nexus {
+    const KNOBS: &'static [Knob] = &[];
+}
+
 fn main() {
-    let run_info = read_args();
-    if run_info.run_type == Execute {
-        panic!("Execution is not supported!");
+    let run_info = read_args::<Nexus>(input_generator!());
+    match run(&run_info) {
+        Ok(()) => run_info.succeed(),
+        Err(error) => run_info.fail(error),
     }
+}
 
+fn load_guest(elf_path: &str) -> Result<Stwo<Local>, HostError> {
+    time_phase(Phase::Setup, || Stwo::new_from_file(elf_path))
+        .map_err(|e| HostError::Prover(format!("failed to load guest program: {e}")))
+}
+
+fn run(run_info: &RunWith) -> Result<(), HostError> {
     let elf_path = std::env::var("ELF_PATH").expect("ELF PATH is missing");
 
     match run_info.run_type {
-        Execute => unreachable!(),
-        Prove => benchmarkable! {
-            // Stwo<T> doesn't derive Clone
-            println!("Loading guest...");
-            let prover: Stwo<Local> = Stwo::new_from_file(&elf_path).expect("failed to load guest program");
+        Execute | Native => unreachable!(),
+        Prove => {
+            let mut last_proof = None;
 
-            println!("Proving execution of vm...");
-            let (view, proof) = prover
-                .prove_with_input(&run_info.private_input, &run_info.public_input)
-                .expect("failed to prove program");
+            benchmarkable! {
+                // Stwo<T> doesn't derive Clone
+                println!("Loading guest...");
+                let prover = load_guest(&elf_path)?;
 
-            output_proof_size(&proof);
+                println!("Proving execution of vm...");
+                let (view, proof) = time_phase(Phase::Prove, || prover.prove_with_input(&run_info.private_input, &run_info.public_input))
+                    .map_err(|e| HostError::Prover(e.to_string()))?;
 
-            println!(
-                " output is {:?}!",
-                view
-                .public_output::<Return>()
-                .expect("failed to deserialize output")
-                );
+                output_proof_size::<Nexus>(&proof);
+
+                let output = view
+                    .public_output::<Return>()
+                    .expect("failed to deserialize output");
+                println!(" output is {:?}!", output);
+                run_info.check_return(&output)?;
 
-            println!(">>>>> Logging\n{}<<<<<", view.logs().expect("failed to retrieve debug logs").join(""));
-        },
+                println!(">>>>> Logging\n{}<<<<<", view.logs().expect("failed to retrieve debug logs").join(""));
+
+                last_proof = Some((output, proof));
+            }
+
+            if let (Some(path), Some((output, proof))) = (&run_info.proof_out, last_proof) {
+                let output = run_info.public_input.with_return(output);
+                write_proof::<Nexus>(path, run_info, Some(&output), &proof, &())?;
+            }
+        }
         Verify => {
             // Stwo<T> doesn't derive Clone
             println!("Loading guest...");
-            let prover: Stwo<Local> =
-                Stwo::new_from_file(&elf_path).expect("failed to load guest program");
+            let prover = load_guest(&elf_path)?;
 
             println!("Proving execution of vm...");
-            let (view, proof) = prover
-                .prove_with_input(&run_info.private_input, &run_info.public_input)
-                .expect("failed to prove program");
+            let (view, proof) = time_phase(Phase::Prove, || {
+                prover.prove_with_input(&run_info.private_input, &run_info.public_input)
+            })
+            .map_err(|e| HostError::Prover(e.to_string()))?;
 
-            output_proof_size(&proof);
+            output_proof_size::<Nexus>(&proof);
 
-            println!(
-                " output is {:?}!",
-                view.public_output::<Return>()
-                    .expect("failed to deserialize output")
-            );
+            let output = view
+                .public_output::<Return>()
+                .expect("failed to deserialize output");
+            println!(" output is {:?}!", output);
+            run_info.check_return(&output)?;
 
             println!(
                 ">>>>> Logging\n{}<<<<<",
@@ -61,9 +127,11 @@ fn main() {
 
             benchmarkable! {
                 print!("Verifying execution...");
-                proof.verify(&view).expect("failed to verify proof");
+                time_phase(Phase::Verify, || proof.verify(&view))
+                    .map_err(|e| HostError::VerificationFailed(e.to_string()))?;
                 println!("  Succeeded!");
             }
         }
     }
+    Ok(())
 }
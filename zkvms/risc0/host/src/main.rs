@@ -1,9 +1,12 @@
 use hex::FromHex;
 use risc0_zkp::core::digest::Digest;
-use risc0_zkvm::{default_executor, default_prover, ExecutorEnv, Receipt};
+use risc0_zkvm::{default_executor, default_prover, ExecutorEnv, Journal, Receipt};
 use zkvms_host_io::{
-    benchmarkable, foreach_input_field, output_proof_size, read_args, Input, Output,
-    RunType::{Execute, Prove, Verify},
+    benchmarkable, check_proof_key, foreach_input_field, input_generator, output_cycles,
+    output_proof_size, output_return, read_args, read_proof, time_phase, write_proof, Capabilities,
+    Configurable, Cycles, HostError, Input, Knob, Output, Phase, ProofSerializer, RunType,
+    RunType::{Execute, Native, Prove, Verify},
+    RunWith,
 };
 
 // https://github.com/risc0/risc0/blob/881e512732eca72849b2d0e263a1242aba3158af/risc0/build/src/lib.rs#L280-L284
@@ -12,6 +15,53 @@ static HELLO_GUEST_ELF: &[u8] = include_bytes!("./guest");
 // Generated by Nix, see zkvms/risc0/default.nix
 static HELLO_GUEST_ID: &str = env!("GUEST_ID");
 
+/// Implements the zkvms_host_io backend hooks for RISC0
+struct RISC0;
+
+impl ProofSerializer for RISC0 {
+    type Proof = Receipt;
+    // Image ID
+    type VerifyingKey = Digest;
+
+    const ENCODING: &'static str = "bincode";
+
+    fn serialize_proof(receipt: &Self::Proof) -> Vec<u8> {
+        bincode::serialize(receipt).expect("failed to serialize receipt")
+    }
+
+    fn deserialize_proof(bytes: &[u8]) -> Result<Self::Proof, HostError> {
+        bincode::deserialize(bytes)
+            .map_err(|e| HostError::InvalidInput(format!("failed to deserialize receipt: {e}")))
+    }
+
+    fn serialize_key(guest_id: &Self::VerifyingKey) -> Vec<u8> {
+        guest_id.as_bytes().to_vec()
+    }
+
+    fn deserialize_key(bytes: &[u8]) -> Result<Self::VerifyingKey, HostError> {
+        Digest::try_from(bytes)
+            .map_err(|e| HostError::InvalidInput(format!("failed to deserialize image ID: {e}")))
+    }
+
+    fn key_digest(guest_id: &Self::VerifyingKey) -> Option<String> {
+        Some(guest_id.to_string())
+    }
+}
+
+impl Capabilities for RISC0 {
+    const RUN_TYPES: &'static [RunType] = &[Execute, Prove, Verify];
+    const PROOF_MODES: &'static [&'static str] = &["composite"];
+    // Verification reports the cycles of the proof it generates
+    const CYCLE_COUNTING: &'static [RunType] = &[Execute, Prove, Verify];
+    const OUTPUT_CHECKING: &'static [RunType] = &[Execute, Prove, Verify];
+    const PROOF_EXPORT: bool = true;
+    const PROOF_IMPORT: bool = true;
+}
+
+impl Configurable for RISC0 {
+    const KNOBS: &'static [Knob] = &[];
+}
+
 fn build_env(input: &Input) -> ExecutorEnv {
     let mut builder = ExecutorEnv::builder();
     foreach_input_field! {
@@ -20,29 +70,64 @@ fn build_env(input: &Input) -> ExecutorEnv {
     builder.build().unwrap()
 }
 
-fn prove(env: ExecutorEnv) -> Receipt {
-    default_prover()
-        .prove(env, HELLO_GUEST_ELF)
-        .expect("Error occured")
-        .receipt
+fn prove(env: ExecutorEnv) -> Result<Receipt, HostError> {
+    let prove_info = time_phase(Phase::Prove, || {
+        default_prover().prove(env, HELLO_GUEST_ELF)
+    })
+    .map_err(|e| HostError::Prover(e.to_string()))?;
+
+    output_cycles(Cycles {
+        cycles: prove_info.stats.user_cycles,
+        paging_cycles: Some(prove_info.stats.paging_cycles),
+        segments: Some(prove_info.stats.segments as u64),
+    });
+
+    Ok(prove_info.receipt)
+}
+
+// https://github.com/risc0/risc0/blob/881e512732eca72849b2d0e263a1242aba3158af/risc0/build/src/lib.rs#L197-L199
+fn guest_id() -> Digest {
+    Digest::from_hex(HELLO_GUEST_ID).unwrap()
 }
 
-fn journal(receipt: Receipt) -> Output {
-    receipt.journal.decode().unwrap()
+fn journal(journal: &Journal) -> Result<Output, HostError> {
+    journal
+        .decode()
+        .map_err(|e| HostError::UnexpectedOutput(format!("failed to decode the journal: {e}")))
 }
 
 fn main() {
-    let run_info = read_args();
+    let run_info = read_args::<RISC0>(input_generator!());
+    match run(&run_info) {
+        Ok(()) => run_info.succeed(),
+        Err(error) => run_info.fail(error),
+    }
+}
 
+fn run(run_info: &RunWith) -> Result<(), HostError> {
     match run_info.run_type {
+        Native => unreachable!(),
         Execute => benchmarkable! {
             // ExecutorEnv does not derive Clone
             let env = build_env(&run_info.input);
             let exec = default_executor();
 
-            let output = default_executor()
-                .execute(env, HELLO_GUEST_ELF)
-                .unwrap()
+            let session = time_phase(Phase::Execute, || default_executor().execute(env, HELLO_GUEST_ELF))
+                .map_err(|e| HostError::GuestPanic(e.to_string()))?;
+
+            // Same counts as when proving, so cycles are comparable across run types
+            output_cycles(Cycles {
+                cycles: session.user_cycles,
+                paging_cycles: Some(session.paging_cycles),
+                segments: Some(session.segments.len() as u64),
+            });
+
+            let session_journal = session.journal.as_ref().ok_or_else(|| {
+                HostError::UnexpectedOutput("the guest didn't commit a journal".to_string())
+            })?;
+            run_info.check_return(&output_return(journal(session_journal)?))?;
+
+            let output = session
                 .receipt_claim
                 .unwrap()
                 .output
@@ -50,28 +135,54 @@ fn main() {
                 .unwrap();
             println!("{:#?}", output);
         },
-        Prove => benchmarkable! {
-            // ExecutorEnv does not derive Clone
-            let env = build_env(&run_info.input);
-            let receipt = prove(env);
+        Prove => {
+            let mut last_receipt = None;
 
-            output_proof_size(&receipt);
+            benchmarkable! {
+                // ExecutorEnv does not derive Clone
+                let env = build_env(&run_info.input);
+                let receipt = prove(env)?;
 
-            println!("Output from journal: {:?}", journal(receipt));
-        },
-        Verify => benchmarkable! {
-            // https://github.com/risc0/risc0/blob/881e512732eca72849b2d0e263a1242aba3158af/risc0/build/src/lib.rs#L197-L199
-            let guest_id: Digest = Digest::from_hex(HELLO_GUEST_ID).unwrap();
+                output_proof_size::<RISC0>(&receipt);
 
-            // ExecutorEnv does not derive Clone
-            let env = build_env(&run_info.input);
-            let receipt = prove(env);
+                let output = journal(&receipt.journal)?;
+                println!("Output from journal: {:?}", output);
+                run_info.check_return(&output_return(output))?;
 
-            output_proof_size(&receipt);
+                last_receipt = Some(receipt);
+            }
 
-            let receipt = receipt.clone();
-            receipt.verify(guest_id.clone()).unwrap();
-            println!("Output from verify: {:?}", journal(receipt));
-        },
+            if let (Some(path), Some(receipt)) = (&run_info.proof_out, last_receipt) {
+                let output = journal(&receipt.journal)?;
+                write_proof::<RISC0>(path, run_info, Some(&output), &receipt, &guest_id())?;
+            }
+        }
+        Verify => {
+            let receipt = match &run_info.proof_in {
+                Some(path) => {
+                    let (receipt, key) = read_proof::<RISC0>(path)?;
+                    check_proof_key::<RISC0>(path, &key, &guest_id())?;
+                    receipt
+                }
+                None => {
+                    // ExecutorEnv does not derive Clone
+                    let env = build_env(&run_info.input);
+                    prove(env)?
+                }
+            };
+
+            output_proof_size::<RISC0>(&receipt);
+
+            // The journal is known before verifying, so only verification is timed
+            let output = journal(&receipt.journal)?;
+            println!("Output from journal: {:?}", output);
+            run_info.check_return(&output_return(output))?;
+
+            benchmarkable! {
+                time_phase(Phase::Verify, || receipt.verify(guest_id()))
+                    .map_err(|e| HostError::VerificationFailed(e.to_string()))?;
+            }
+        }
     }
+    Ok(())
 }
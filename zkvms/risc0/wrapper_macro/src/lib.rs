@@ -5,7 +5,7 @@ mod parse_fn;
 use crate::parse_fn::FunctionDefinition;
 
 /// Create a body, which reads all inputs, stores them in variables, then
-/// commits the ones, defined as public in `default_public_input.toml` to the
+/// commits the ones, annotated with `#[public]` to the
 /// journal and finally executes the guest entrypoint function with those
 /// arguments, committing its output.
 ///
@@ -1,12 +1,65 @@
-use sp1_sdk::{EnvProver, ProverClient, SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey};
+use sp1_sdk::{
+    EnvProver, HashableKey, ProverClient, SP1ProofWithPublicValues, SP1PublicValues, SP1Stdin,
+    SP1VerifyingKey,
+};
 use zkvms_host_io::{
-    benchmarkable, foreach_input_field, output_proof_size, read_args, Input,
-    RunType::{Execute, Prove, Verify},
+    benchmarkable, check_proof_key, foreach_input_field, input_generator, output_cycles,
+    output_proof_size, output_return, read_args, read_proof, time_phase, write_proof, Capabilities,
+    Configurable, Cycles, HostError, Input, Knob, Output, Phase, ProofSerializer, RunType,
+    RunType::{Execute, Native, Prove, Verify},
+    RunWith,
 };
 
 /// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
 pub const FIBONACCI_ELF: &[u8] = include_bytes!("./guest");
 
+/// Implements the zkvms_host_io backend hooks for SP1
+struct SP1;
+
+impl ProofSerializer for SP1 {
+    type Proof = SP1ProofWithPublicValues;
+    type VerifyingKey = SP1VerifyingKey;
+
+    // SP1ProofWithPublicValues::save uses bincode as well
+    const ENCODING: &'static str = "bincode";
+
+    fn serialize_proof(proof: &Self::Proof) -> Vec<u8> {
+        bincode::serialize(proof).expect("failed to serialize proof")
+    }
+
+    fn deserialize_proof(bytes: &[u8]) -> Result<Self::Proof, HostError> {
+        bincode::deserialize(bytes)
+            .map_err(|e| HostError::InvalidInput(format!("failed to deserialize proof: {e}")))
+    }
+
+    fn serialize_key(vk: &Self::VerifyingKey) -> Vec<u8> {
+        bincode::serialize(vk).expect("failed to serialize verifying key")
+    }
+
+    fn deserialize_key(bytes: &[u8]) -> Result<Self::VerifyingKey, HostError> {
+        bincode::deserialize(bytes).map_err(|e| {
+            HostError::InvalidInput(format!("failed to deserialize verifying key: {e}"))
+        })
+    }
+
+    fn key_digest(vk: &Self::VerifyingKey) -> Option<String> {
+        Some(vk.bytes32())
+    }
+}
+
+impl Capabilities for SP1 {
+    const RUN_TYPES: &'static [RunType] = &[Execute, Prove, Verify];
+    const PROOF_MODES: &'static [&'static str] = &["core"];
+    const CYCLE_COUNTING: &'static [RunType] = &[Execute];
+    const OUTPUT_CHECKING: &'static [RunType] = &[Execute, Prove, Verify];
+    const PROOF_EXPORT: bool = true;
+    const PROOF_IMPORT: bool = true;
+}
+
+impl Configurable for SP1 {
+    const KNOBS: &'static [Knob] = &[];
+}
+
 fn build_stdin(input: &Input) -> SP1Stdin {
     let mut stdin = SP1Stdin::new();
     foreach_input_field! {
@@ -15,47 +68,95 @@ fn build_stdin(input: &Input) -> SP1Stdin {
     stdin
 }
 
-fn prove(client: &EnvProver, stdin: SP1Stdin) -> (SP1ProofWithPublicValues, SP1VerifyingKey) {
-    let (pk, vk) = client.setup(FIBONACCI_ELF);
-    let proof = client
-        .prove(&pk, &stdin)
-        .run()
-        .expect("failed to generate proof");
-    (proof, vk)
+fn prove(
+    client: &EnvProver,
+    stdin: SP1Stdin,
+) -> Result<(SP1ProofWithPublicValues, SP1VerifyingKey), HostError> {
+    let (pk, vk) = time_phase(Phase::Setup, || client.setup(FIBONACCI_ELF));
+    let proof = time_phase(Phase::Prove, || client.prove(&pk, &stdin).run())
+        .map_err(|e| HostError::Prover(e.to_string()))?;
+    Ok((proof, vk))
+}
+
+// Public inputs and the return value are committed in order
+fn public_output(public_values: &SP1PublicValues) -> Output {
+    public_values.clone().read::<Output>()
 }
 
 fn main() {
-    let run_info = read_args();
+    let run_info = read_args::<SP1>(input_generator!());
+    match run(&run_info) {
+        Ok(()) => run_info.succeed(),
+        Err(error) => run_info.fail(error),
+    }
+}
+
+fn run(run_info: &RunWith) -> Result<(), HostError> {
     let stdin = build_stdin(&run_info.input);
 
     sp1_sdk::utils::setup_logger();
     let client = ProverClient::new();
 
     match run_info.run_type {
+        Native => unreachable!(),
         Execute => benchmarkable! {
-            let (output, report) = client.execute(FIBONACCI_ELF, &stdin).run().unwrap();
+            let (output, report) = time_phase(Phase::Execute, || client.execute(FIBONACCI_ELF, &stdin).run())
+                .map_err(|e| HostError::GuestPanic(e.to_string()))?;
 
             println!("Program executed successfully.");
             println!("{:?}", output);
+            run_info.check_return(&output_return(public_output(&output)))?;
             println!("Number of cycles: {}", report.total_instruction_count());
+            output_cycles(Cycles {
+                cycles: report.total_instruction_count(),
+                ..Default::default()
+            });
         },
-        Prove => benchmarkable! {
-            let (proof, _) = prove(&client, stdin.clone());
+        Prove => {
+            let mut last_proof = None;
+
+            benchmarkable! {
+                let (proof, vk) = prove(&client, stdin.clone())?;
 
-            output_proof_size(&proof);
+                output_proof_size::<SP1>(&proof);
 
-            println!("Successfully generated proof!");
-        },
+                println!("Successfully generated proof!");
+                run_info.check_return(&output_return(public_output(&proof.public_values)))?;
+
+                last_proof = Some((proof, vk));
+            }
+
+            if let (Some(path), Some((proof, vk))) = (&run_info.proof_out, last_proof) {
+                let output = public_output(&proof.public_values);
+                write_proof::<SP1>(path, run_info, Some(&output), &proof, &vk)?;
+            }
+        }
         Verify => {
-            let (proof, vk) = prove(&client, stdin.clone());
-            println!("Successfully generated proof!");
+            let (proof, vk) = match &run_info.proof_in {
+                Some(path) => {
+                    let (proof, key) = read_proof::<SP1>(path)?;
+                    let (_, vk) = time_phase(Phase::Setup, || client.setup(FIBONACCI_ELF));
+                    check_proof_key::<SP1>(path, &key, &vk)?;
+                    (proof, vk)
+                }
+                None => {
+                    let (proof, vk) = prove(&client, stdin.clone())?;
+                    println!("Successfully generated proof!");
+                    (proof, vk)
+                }
+            };
+
+            output_proof_size::<SP1>(&proof);
 
-            output_proof_size(&proof);
+            // The public values are known before verifying, so only verification is timed
+            run_info.check_return(&output_return(public_output(&proof.public_values)))?;
 
             benchmarkable! {
-                client.verify(&proof, &vk).expect("failed to verify proof");
+                time_phase(Phase::Verify, || client.verify(&proof, &vk))
+                    .map_err(|e| HostError::VerificationFailed(e.to_string()))?;
                 println!("Successfully verified proof!");
             }
         }
     }
+    Ok(())
 }
@@ -1,4 +1,3 @@
-use anyhow::{bail, Result};
 use std::{env, fs::read, time::Instant};
 use zkm_sdk::{
     prover::ClientCfg,
@@ -7,29 +6,126 @@ use zkm_sdk::{
 };
 
 use zkvms_host_io::{
-    benchmarkable, output_proof_size, read_args,
-    RunType::{Execute, Prove, Verify},
+    benchmarkable, input_generator, output_cycles, output_proof_size, read_args, write_proof,
+    Capabilities, Configurable, Cycles, HostError, Knob, KnobType, Phase, PhaseTimer,
+    ProofSerializer, Return, RunType,
+    RunType::{Execute, Native, Prove, Verify},
+    RunWith,
 };
 
+/// Implements the zkvms_host_io backend hooks for ZKM
+struct ZKM;
+
+impl ProofSerializer for ZKM {
+    type Proof = ProverResult;
+    type VerifyingKey = ();
+
+    // The final (SNARK) proof is already serialized by the prover
+    const ENCODING: &'static str = "json (proof_with_public_inputs)";
+
+    fn serialize_proof(prover_result: &Self::Proof) -> Vec<u8> {
+        prover_result.proof_with_public_inputs.clone()
+    }
+
+    fn deserialize_proof(bytes: &[u8]) -> Result<Self::Proof, HostError> {
+        Ok(ProverResult {
+            proof_with_public_inputs: bytes.to_vec(),
+            ..Default::default()
+        })
+    }
+
+    fn serialize_key(_: &Self::VerifyingKey) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn deserialize_key(_: &[u8]) -> Result<Self::VerifyingKey, HostError> {
+        Ok(())
+    }
+}
+
+impl Capabilities for ZKM {
+    // Only on-chain verification is supported
+    const RUN_TYPES: &'static [RunType] = &[Execute, Prove];
+    const PROOF_MODES: &'static [&'static str] = &["groth16"];
+    const CYCLE_COUNTING: &'static [RunType] = &[Execute, Prove];
+    const OUTPUT_CHECKING: &'static [RunType] = &[Execute, Prove];
+    const PROOF_EXPORT: bool = true;
+    const PROOF_IMPORT: bool = false;
+}
+
+impl Configurable for ZKM {
+    const KNOBS: &'static [Knob] = &[
+        Knob {
+            name: "SEG_SIZE",
+            kind: KnobType::Integer,
+            default: "65536",
+            description: "Size of the segments, into which the execution is split",
+        },
+        Knob {
+            name: "PROOF_RESULTS_PATH",
+            kind: KnobType::Path,
+            default: "/tmp/contracts",
+            description: "Directory, into which the proof results are written",
+        },
+        Knob {
+            name: "VERIFYING_KEY_PATH",
+            kind: KnobType::Path,
+            default: "/tmp/input",
+            description: "Directory of the proving and verifying keys",
+        },
+        Knob {
+            name: "SNARK_SETUP",
+            kind: KnobType::Boolean,
+            default: "false",
+            description: "Whether to run the setup of the SNARK circuit",
+        },
+    ];
+}
+
 async fn get_proof(
     prover_client: &mut ProverClient,
     prover_input: &mut ProverInput,
-) -> ProverResult {
-    let proving_result = prover_client.prover.prove(&prover_input, None).await;
-
-    if let Ok(Some(prover_result)) = proving_result {
-        prover_result
+) -> Result<ProverResult, HostError> {
+    // With execute_only the prover only runs the guest program
+    let error = if prover_input.execute_only {
+        HostError::GuestPanic
     } else {
-        panic!("Failed to generate proof!");
+        HostError::Prover
+    };
+
+    match prover_client.prover.prove(&prover_input, None).await {
+        Ok(Some(prover_result)) => {
+            output_cycles(Cycles {
+                cycles: prover_result.total_steps,
+                ..Default::default()
+            });
+            Ok(prover_result)
+        }
+        Ok(None) => Err(error("the prover returned no result".to_string())),
+        Err(e) => Err(error(e.to_string())),
     }
 }
 
-async fn execute(prover_client: &mut ProverClient, prover_input: &mut ProverInput) {
-    let prover_result = get_proof(prover_client, prover_input).await;
+// The guest commits only its return value
+fn guest_return(prover_result: &ProverResult) -> Return {
+    bincode::deserialize::<Return>(&prover_result.output_stream)
+        .expect("failed to deserialize output")
+}
+
+async fn execute(
+    prover_client: &mut ProverClient,
+    prover_input: &mut ProverInput,
+) -> Result<ProverResult, HostError> {
+    let prover_result = {
+        let _timer = PhaseTimer::start(Phase::Execute);
+        get_proof(prover_client, prover_input).await?
+    };
 
     prover_client
         .print_guest_execution_output(true, &prover_result)
-        .expect("print guest program excution's output false.")
+        .expect("print guest program excution's output false.");
+
+    Ok(prover_result)
 }
 
 async fn prove(
@@ -37,29 +133,37 @@ async fn prove(
     prover_input: &mut ProverInput,
     key_path: &String,
     proof_results_path: &String,
-) {
-    let prover_result = get_proof(prover_client, prover_input).await;
+) -> Result<ProverResult, HostError> {
+    let prover_result = {
+        let _timer = PhaseTimer::start(Phase::Prove);
+        get_proof(prover_client, prover_input).await?
+    };
 
-    output_proof_size(&prover_result);
+    output_proof_size::<ZKM>(&prover_result);
 
     prover_client
         .process_proof_results(&prover_result, &prover_input, &proof_results_path)
         .expect("process proof results error");
+
+    Ok(prover_result)
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let run_info = read_args();
-    if run_info.run_type == Verify {
-        panic!("Off-chain verification is not supported!");
+async fn main() {
+    let run_info = read_args::<ZKM>(input_generator!());
+    match run(&run_info).await {
+        Ok(()) => run_info.succeed(),
+        Err(error) => run_info.fail(error),
     }
+}
 
-    let seg_size: u32 = run_info.env_then_or("SEG_SIZE", |seg| seg.parse::<u32>().ok(), 65536);
+async fn run(run_info: &RunWith) -> Result<(), HostError> {
+    let seg_size = run_info.config.get::<u32>("SEG_SIZE");
 
     let elf_path = env::var("ELF_PATH").expect("ELF PATH is missing");
 
-    let proof_results_path = run_info.env_or("PROOF_RESULTS_PATH", "/tmp/contracts");
-    let key_path = run_info.env_or("VERIFYING_KEY_PATH", "/tmp/input");
+    let proof_results_path = run_info.config.get::<String>("PROOF_RESULTS_PATH");
+    let key_path = run_info.config.get::<String>("VERIFYING_KEY_PATH");
 
     let mut client_config = ClientCfg {
         zkm_prover_type: "local".to_string(),
@@ -81,7 +185,7 @@ async fn main() -> Result<()> {
     let mut prover_input = ProverInput {
         elf: read(elf_path).unwrap(),
         execute_only: run_info.run_type == Execute,
-        snark_setup: run_info.env_then_or("SNARK_SETUP", |flag| flag.parse::<bool>().ok(), false),
+        snark_setup: run_info.config.get::<bool>("SNARK_SETUP"),
         seg_size,
         proof_results_path: proof_results_path.clone(),
         public_inputstream,
@@ -94,13 +198,27 @@ async fn main() -> Result<()> {
     match run_info.run_type {
         // only excute the guest program without generating the proof.
         Execute => benchmarkable! {
-            execute(&mut prover_client, &mut prover_input).await;
+            let prover_result = execute(&mut prover_client, &mut prover_input).await?;
+            run_info.check_return(&guest_return(&prover_result))?;
         },
         // excute the guest program and generate the proof
-        Prove => benchmarkable! {
-            prove(&mut prover_client, &mut prover_input, &key_path, &proof_results_path).await;
-        },
-        Verify => unreachable!(),
+        Prove => {
+            let mut last_proof = None;
+
+            benchmarkable! {
+                let prover_result = prove(&mut prover_client, &mut prover_input, &key_path, &proof_results_path).await?;
+                run_info.check_return(&guest_return(&prover_result))?;
+                last_proof = Some(prover_result);
+            }
+
+            if let (Some(path), Some(prover_result)) = (&run_info.proof_out, last_proof) {
+                let output = run_info
+                    .public_input
+                    .with_return(guest_return(&prover_result));
+                write_proof::<ZKM>(path, run_info, Some(&output), &prover_result, &())?;
+            }
+        }
+        Verify | Native => unreachable!(),
     }
     let end = Instant::now();
     let elapsed = end.duration_since(start);
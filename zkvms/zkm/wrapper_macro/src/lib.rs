@@ -8,7 +8,8 @@ use crate::parse_fn::FunctionDefinition;
 /// variables, then executes the guest entrypoint function with those arguments
 /// and commits its output.
 ///
-/// `default_public_input.toml` shows which variables are public.
+/// The `#[public]` and `#[private]` argument annotations show which variables
+/// are public.
 ///
 /// # Usage
 ///
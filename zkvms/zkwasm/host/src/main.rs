@@ -1,15 +1,147 @@
+use std::fs::{create_dir_all, read_dir};
 use std::io::{self, Write};
+use std::path::Path;
 use std::process::{Command, Stdio};
 use zkvms_host_io::{
-    benchmarkable, foreach_private_input_field, foreach_public_input_field, output_proof_size_raw,
-    read_args, PrivateInput, PublicInput,
-    RunType::{Execute, Prove, Verify},
+    benchmarkable, foreach_private_input_field, foreach_public_input_field, input_generator,
+    output_cycles, output_proof_size_raw, read_args, read_proof, run_child, run_child_output,
+    time_phase, write_proof, Capabilities, Configurable, Cycles, HostError, Knob, KnobType, Phase,
+    PrivateInput, ProofSerializer, PublicInput, RunType,
+    RunType::{Execute, Native, Prove, Verify},
     RunWith,
 };
 
 static PUBLIC_INPUT_PATH: &str = "public_input.bin";
 static PRIVATE_INPUT_PATH: &str = "private_input.bin";
 
+/// zkWasm stores proofs as files. The proof size is that of the transcript
+/// file, measured as-is, while proof files contain the whole output directory.
+static TRANSCRIPT_ENCODING: &str = "raw (prog.0.transcript.data)";
+
+/// Implements the zkvms_host_io backend hooks for zkWasm
+struct ZkWasm;
+
+/// Names and contents of all files inside zkWasm's output directory
+type ProofFiles = Vec<(String, Vec<u8>)>;
+
+/// Every file is stored as its name and contents, each prefixed by their
+/// length (8 bytes, little endian).
+impl ProofSerializer for ZkWasm {
+    type Proof = ProofFiles;
+    // The verifying key is inside the output directory
+    type VerifyingKey = ();
+
+    const ENCODING: &'static str = "length-prefixed output dir";
+
+    fn serialize_proof(files: &Self::Proof) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (name, contents) in files {
+            bytes.extend((name.len() as u64).to_le_bytes());
+            bytes.extend(name.as_bytes());
+            bytes.extend((contents.len() as u64).to_le_bytes());
+            bytes.extend(contents);
+        }
+        bytes
+    }
+
+    fn deserialize_proof(mut bytes: &[u8]) -> Result<Self::Proof, HostError> {
+        let mut files = Vec::new();
+        while !bytes.is_empty() {
+            let name = String::from_utf8(next_chunk(&mut bytes)?)
+                .map_err(|_| HostError::InvalidInput("invalid proof file name".to_string()))?;
+            files.push((name, next_chunk(&mut bytes)?));
+        }
+        Ok(files)
+    }
+
+    fn serialize_key(_: &Self::VerifyingKey) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn deserialize_key(_: &[u8]) -> Result<Self::VerifyingKey, HostError> {
+        Ok(())
+    }
+}
+
+impl Capabilities for ZkWasm {
+    const RUN_TYPES: &'static [RunType] = &[Execute, Prove, Verify];
+    // Selected with ZKWASM_SCHEME
+    const PROOF_MODES: &'static [&'static str] = &["shplonk", "gwc"];
+    const CYCLE_COUNTING: &'static [RunType] = &[Execute];
+    // zkWasm doesn't expose the value, returned by the guest
+    const OUTPUT_CHECKING: &'static [RunType] = &[];
+    const PROOF_EXPORT: bool = true;
+    const PROOF_IMPORT: bool = true;
+}
+
+impl Configurable for ZkWasm {
+    const KNOBS: &'static [Knob] = &[
+        Knob {
+            name: "ZKWASM_K",
+            kind: KnobType::Integer,
+            default: "19",
+            description: "Size of the circuit, as a power of two",
+        },
+        Knob {
+            name: "ZKWASM_SCHEME",
+            kind: KnobType::Choice(&["shplonk", "gwc"]),
+            default: "shplonk",
+            description: "Polynomial commitment scheme",
+        },
+        Knob {
+            name: "ZKWASM_OUTPUT",
+            kind: KnobType::Path,
+            default: "/tmp/output",
+            description: "Directory, into which zkWasm writes the proof",
+        },
+        Knob {
+            name: "ZKWASM_PARAMS",
+            kind: KnobType::Path,
+            default: "/tmp/params",
+            description: "Directory of the setup parameters, used for verification",
+        },
+    ];
+}
+
+/// Removes a length-prefixed chunk from the start of bytes
+fn next_chunk(bytes: &mut &[u8]) -> Result<Vec<u8>, HostError> {
+    let truncated = || HostError::InvalidInput("truncated proof".to_string());
+    let (len, rest) = bytes.split_first_chunk::<8>().ok_or_else(truncated)?;
+    let len = usize::try_from(u64::from_le_bytes(*len)).map_err(|_| truncated())?;
+    let chunk = rest.get(..len).ok_or_else(truncated)?;
+    *bytes = &rest[len..];
+    Ok(chunk.to_vec())
+}
+
+fn read_output_dir(output: &str) -> ProofFiles {
+    read_dir(output)
+        .expect("couldn't read output directory!")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_file())
+        .map(|path| {
+            (
+                path.file_name().unwrap().to_string_lossy().to_string(),
+                std::fs::read(&path).unwrap(),
+            )
+        })
+        .collect()
+}
+
+/// Size of the proof transcript, which zkwasm-cli wrote into the output
+/// directory
+fn transcript_size(output: &str) -> Result<u64, HostError> {
+    std::fs::metadata(output.to_string() + "/prog.0.transcript.data")
+        .map(|metadata| metadata.len())
+        .map_err(|e| HostError::Prover(format!("couldn't read proof transcript: {e}")))
+}
+
+fn write_output_dir(output: &str, files: &ProofFiles) {
+    create_dir_all(output).expect("couldn't create output directory!");
+    for (name, contents) in files {
+        std::fs::write(Path::new(output).join(name), contents).unwrap();
+    }
+}
+
 /// Creates an anonymous function which takes `run_info`, "serializes" the
 /// specified input, outputs it into a file and returns a "path:<PATH>"
 /// argument, ready to be passed to zkWasm.
@@ -49,75 +181,141 @@ fn zkwasm_command(subcmd: &str) -> Command {
     command
 }
 
-fn run(cmd: &mut Command) {
-    assert!(cmd.status().expect("couldn't execute command!").success());
+/// Runs a zkwasm-cli command, which is killed on timeout. If it fails,
+/// returns the error created by `error` from a description of the failure.
+fn run_command(cmd: &mut Command, error: fn(String) -> HostError) -> Result<(), HostError> {
+    let status = run_child(cmd).map_err(|e| error(format!("couldn't execute zkwasm-cli: {e}")))?;
+    if !status.success() {
+        return Err(error(format!("zkwasm-cli exited with {status}")));
+    }
+    Ok(())
+}
+
+/// Like `run_command`, but returns the standard output of the command.
+fn run_command_output(
+    cmd: &mut Command,
+    error: fn(String) -> HostError,
+) -> Result<String, HostError> {
+    let output = run_child_output(cmd.stdout(Stdio::piped()))
+        .map_err(|e| error(format!("couldn't execute zkwasm-cli: {e}")))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    print!("{stdout}");
+    if !output.status.success() {
+        return Err(error(format!("zkwasm-cli exited with {}", output.status)));
+    }
+    Ok(stdout)
+}
+
+/// Finds the amount of executed instructions in the output of a dry run,
+/// which has a line "total guest instructions used N".
+fn guest_instructions(stdout: &str) -> Option<u64> {
+    stdout.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("total guest instructions used")?
+            .trim()
+            .parse()
+            .ok()
+    })
 }
 
 fn main() {
-    let run_info = read_args();
+    let run_info = read_args::<ZkWasm>(input_generator!());
+    match run(&run_info) {
+        Ok(()) => run_info.succeed(),
+        Err(error) => run_info.fail(error),
+    }
+}
 
-    let k = run_info.env_or("ZKWASM_K", "19");
+fn run(run_info: &RunWith) -> Result<(), HostError> {
+    let k = run_info.config.get::<u32>("ZKWASM_K");
 
-    let scheme = run_info.env_or("ZKWASM_SCHEME", "shplonk");
+    let scheme = run_info.config.get::<String>("ZKWASM_SCHEME");
 
-    run(zkwasm_command("setup")
-        .arg("-k")
-        .arg(k)
-        .arg("--scheme")
-        .arg(scheme));
+    time_phase(Phase::Setup, || {
+        run_command(
+            zkwasm_command("setup")
+                .arg("-k")
+                .arg(k.to_string())
+                .arg("--scheme")
+                .arg(scheme),
+            HostError::Prover,
+        )
+    })?;
 
     let public_input = build_input!(
         run_info.public_input,
         PUBLIC_INPUT_PATH,
         foreach_public_input_field
-    )(&run_info);
+    )(run_info);
 
     let private_input = build_input!(
         run_info.private_input,
         PRIVATE_INPUT_PATH,
         foreach_private_input_field
-    )(&run_info);
+    )(run_info);
 
-    let output = run_info.env_or("ZKWASM_OUTPUT", "/tmp/output");
+    let output = run_info.config.get::<String>("ZKWASM_OUTPUT");
 
-    let params = run_info.env_or("ZKWASM_PARAMS", "/tmp/params");
+    let params = run_info.config.get::<String>("ZKWASM_PARAMS");
 
     match run_info.run_type {
+        Native => unreachable!(),
         Execute => benchmarkable! {
-            run(zkwasm_command("dry-run")
-                .arg("--public").arg(public_input.clone())
-                .arg("--private").arg(private_input.clone())
-                .arg("--output").arg(output.clone()));
-        },
-        Prove => benchmarkable! {
-            run(zkwasm_command("prove")
+            let stdout = time_phase(Phase::Execute, || run_command_output(zkwasm_command("dry-run")
                 .arg("--public").arg(public_input.clone())
                 .arg("--private").arg(private_input.clone())
-                .arg("--output").arg(output.clone()));
+                .arg("--output").arg(output.clone()), HostError::GuestPanic))?;
 
-            let proofSize = std::fs::metadata(output.clone() + "/prog.0.transcript.data").unwrap().len();
-            output_proof_size_raw(proofSize as usize);
+            if let Some(cycles) = guest_instructions(&stdout) {
+                output_cycles(Cycles {
+                    cycles,
+                    ..Default::default()
+                });
+            }
         },
+        Prove => {
+            benchmarkable! {
+                time_phase(Phase::Prove, || run_command(zkwasm_command("prove")
+                    .arg("--public").arg(public_input.clone())
+                    .arg("--private").arg(private_input.clone())
+                    .arg("--output").arg(output.clone()), HostError::Prover))?;
+
+                let proofSize = transcript_size(&output)?;
+                output_proof_size_raw(proofSize as usize, TRANSCRIPT_ENCODING);
+            }
+
+            if let Some(path) = &run_info.proof_out {
+                // Outputs are only written to the transcript
+                write_proof::<ZkWasm>(path, run_info, None, &read_output_dir(&output), &())?;
+            }
+        }
         Verify => {
-            run(zkwasm_command("prove")
-                .arg("--public")
-                .arg(public_input)
-                .arg("--private")
-                .arg(private_input)
-                .arg("--output")
-                .arg(output.clone()));
-
-            let proofSize = std::fs::metadata(output.clone() + "/prog.0.transcript.data")
-                .unwrap()
-                .len();
-            output_proof_size_raw(proofSize as usize);
+            match &run_info.proof_in {
+                Some(path) => write_output_dir(&output, &read_proof::<ZkWasm>(path)?.0),
+                None => time_phase(Phase::Prove, || {
+                    run_command(
+                        zkwasm_command("prove")
+                            .arg("--public")
+                            .arg(public_input)
+                            .arg("--private")
+                            .arg(private_input)
+                            .arg("--output")
+                            .arg(output.clone()),
+                        HostError::Prover,
+                    )
+                })?,
+            }
+
+            let proofSize = transcript_size(&output)?;
+            output_proof_size_raw(proofSize as usize, TRANSCRIPT_ENCODING);
 
             benchmarkable! {
-                run(Command::new("zkwasm-cli")
+                time_phase(Phase::Verify, || run_command(Command::new("zkwasm-cli")
                     .arg("--params").arg(params.clone())
                     .arg("prog").arg("verify")
-                    .arg("--output").arg(output.clone()));
+                    .arg("--output").arg(output.clone()), HostError::VerificationFailed))?;
             }
         }
     }
+    Ok(())
 }
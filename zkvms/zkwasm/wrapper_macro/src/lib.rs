@@ -34,7 +34,8 @@ fn insert_reads(
 /// result.
 ///
 /// Inputs are read via the read! macro (defined in the zkWasm wrapper_macro
-/// crate). Their public status is dependent on `default_public_input.toml`.
+/// crate). Their public status is dependent on the `#[public]` and `#[private]`
+/// annotations of the entrypoint arguments.
 ///
 /// # Usage
 ///
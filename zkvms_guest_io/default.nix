@@ -1,12 +1,12 @@
-{ rev, craneLib-default, guest, zkvms, hostPackages, lib, metacraft-labs
-, benchexec, }:
+{ rev, craneLib-default, guest, zkvms, hostPackages, nativePackage, lib
+, metacraft-labs, benchexec, }:
 let
   commonArgs = {
     name = "${guest}";
 
     buildInputs = [ benchexec ]
       ++ (lib.foldr (zkvm: accum: accum ++ [ hostPackages."${zkvm}/${guest}" ])
-        [ ] zkvms);
+        [ nativePackage ] zkvms);
 
     src = lib.fileset.toSource {
       root = ./.;
@@ -22,8 +22,18 @@ let
         (hostPackages."${zkvm}/${guest}" + "/bin/${zkvm}_${guest}")
       ]) + "," + accum) "" zkvms;
 
+    NATIVE = nativePackage + "/bin/native_${guest}";
+
+    # verify-envelope is also installed
+    meta.mainProgram = "zkvms_guest_io";
+
     postPatch = ''
       sed -i 's|"runexec"|"${benchexec}/bin/runexec"|' ./src/main.rs
+      sed -i 's|"../../zkvms_host_io/src/format.rs"|"${../zkvms_host_io/src/format.rs}"|' ./src/main.rs
+      sed -i 's|"../../zkvms_host_io/src/metrics.rs"|"${../zkvms_host_io/src/metrics.rs}"|' ./src/main.rs
+      sed -i 's|"../../zkvms_host_io/src/sweep.rs"|"${../zkvms_host_io/src/sweep.rs}"|' ./src/main.rs
+      sed -i 's|"../../../zkvms_host_io/src/envelope.rs"|"${../zkvms_host_io/src/envelope.rs}"|' ./src/bin/verify-envelope.rs
+      sed -i 's|"../../../zkvms_host_io/src/metrics.rs"|"${../zkvms_host_io/src/metrics.rs}"|' ./src/bin/verify-envelope.rs
     '';
   };
 
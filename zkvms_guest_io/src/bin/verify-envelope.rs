@@ -0,0 +1,134 @@
+use clap::Parser;
+use std::fs::{read_to_string, remove_file};
+use std::process::{exit, Command};
+
+// The path is replaced by zkvms_guest_io/default.nix
+#[path = "../../../zkvms_host_io/src/envelope.rs"]
+#[allow(dead_code)]
+mod envelope;
+use envelope::Envelope;
+
+// The path is replaced by zkvms_guest_io/default.nix
+#[path = "../../../zkvms_host_io/src/metrics.rs"]
+#[allow(dead_code)]
+mod metrics;
+use metrics::{ErrorKind, HostCapabilities, RunMetrics};
+
+/// A CLI tool for verifying proof files, written by a zkVM host with
+/// --proof-out.
+/// The proof is verified by the host of the zkVM, which generated it, so this
+/// binary can only verify proofs of its own guest program.
+#[derive(Parser, Debug)]
+#[command(about, long_about = None)]
+struct Cli {
+    /// Path to the proof file
+    proof: String,
+
+    /// Verify the proof multiple times
+    #[arg(short, long, default_value_t = 1)]
+    repeat: usize,
+}
+
+static METRICS_TEMP_OUTPUT_PATH: &str = "/tmp/verify_envelope_metrics";
+
+fn main() {
+    let cli = Cli::parse();
+
+    let envelope = Envelope::read(&cli.proof).unwrap_or_else(|message| {
+        println!("Result:    invalid");
+        println!("The proof is unusable, {message}!");
+        exit(1);
+    });
+    let header = &envelope.header;
+
+    println!("zkVM:      {} ({})", header.zkvm_name, header.zkvm_rev);
+    println!("Program:   {}", header.program_name);
+    println!("Signature: {}", header.signature.trim());
+    println!("Input:     {}", header.public_input);
+    println!("Output:    {}", header.output);
+    if let Some(digest) = &header.key_digest {
+        println!("Key:       {digest}");
+    }
+    println!("Encoding:  {}", header.proof_encoding);
+
+    // This is set by zkvms_guest_io/default.nix
+    let zkvm_guest_command = env!("PROGRAMS")
+        .split(',')
+        .filter(|x| !x.is_empty())
+        .map(|x| x.split('|').collect::<Vec<&str>>())
+        .find(|fields| fields[0] == header.zkvm_name && fields[2] == header.program_name)
+        .map(|fields| fields[4]);
+
+    let Some(zkvm_guest_command) = zkvm_guest_command else {
+        println!("Result:    unsupported");
+        println!(
+            "There is no {} host for {} in this build!",
+            header.zkvm_name, header.program_name
+        );
+        exit(2);
+    };
+
+    let capabilities = HostCapabilities::query(zkvm_guest_command).unwrap_or_else(|message| {
+        println!("Result:    error");
+        println!("{message}!");
+        exit(3);
+    });
+    if !capabilities.proof_import {
+        println!("Result:    unsupported");
+        println!("{} cannot verify a stored proof!", header.zkvm_name);
+        exit(2);
+    }
+
+    let _ = remove_file(METRICS_TEMP_OUTPUT_PATH);
+
+    let status = Command::new(zkvm_guest_command)
+        .arg("verify")
+        .args(["--proof-in", &cli.proof])
+        .arg("--benchmark")
+        .args(["--repeat", &cli.repeat.to_string()])
+        .args(["--metrics-output", METRICS_TEMP_OUTPUT_PATH])
+        .status()
+        .expect("failed to run zkVM host");
+
+    let host_exit_code = status.code().unwrap_or(-1);
+    if !status.success() {
+        let (result, exit_code) = match ErrorKind::from_exit_code(host_exit_code) {
+            ErrorKind::VerificationFailed
+            | ErrorKind::InvalidInput
+            | ErrorKind::UnexpectedOutput => ("invalid", 1),
+            ErrorKind::Unsupported => ("unsupported", 2),
+            _ => ("error", 3),
+        };
+        println!("Result:    {result}");
+        println!("The host exited with status code {host_exit_code}");
+        exit(exit_code);
+    }
+
+    let metrics = read_to_string(METRICS_TEMP_OUTPUT_PATH)
+        .map_err(|e| format!("Couldn't read metrics file \"{METRICS_TEMP_OUTPUT_PATH}\": {e}"))
+        .and_then(|metrics| {
+            serde_json::from_str::<RunMetrics>(&metrics).map_err(|e| {
+                format!("Couldn't parse metrics file \"{METRICS_TEMP_OUTPUT_PATH}\": {e}")
+            })
+        });
+    let (runs, mean) = match metrics {
+        Ok(RunMetrics {
+            runs,
+            mean: Some(mean),
+            ..
+        }) => (runs, mean),
+        Ok(_) => error(host_exit_code, "The host didn't report a verification time"),
+        Err(message) => error(host_exit_code, &message),
+    };
+
+    println!("Result:    valid");
+    println!("Verification time: {mean}s (mean of {runs} runs)");
+}
+
+/// Reports an error of the host, which didn't fail, but whose metrics are
+/// missing or incomplete
+fn error(host_exit_code: i32, message: &str) -> ! {
+    println!("Result:    error");
+    println!("{message}! The host exited with status code {host_exit_code}");
+    exit(3);
+}
@@ -1,12 +1,34 @@
 use clap::Parser;
 use itertools::Itertools;
-use json::{object, parse, JsonValue, Null};
+use serde_json::Value;
 use smbioslib::*;
-use std::fs::{read_to_string, OpenOptions};
-use std::io::{Error, Write};
+use std::fs::{read_to_string, remove_file};
+use std::io::Error;
+use std::path::Path;
 use std::process::{Command, Stdio};
+use std::time::Duration;
 use sysinfo::System;
 
+// The path is replaced by zkvms_guest_io/default.nix
+#[path = "../../zkvms_host_io/src/format.rs"]
+mod format;
+use format::Format;
+
+// The path is replaced by zkvms_guest_io/default.nix
+#[path = "../../zkvms_host_io/src/metrics.rs"]
+#[allow(dead_code)]
+mod metrics;
+use metrics::{
+    Benchmark, Cpu, ErrorInfo, ErrorKind, Hardware, HostCapabilities, NativeOverhead,
+    ProgramMetrics, RunMetrics, Status, SweepMetrics, SweepPoint, SCHEMA_VERSION,
+};
+
+// The path is replaced by zkvms_guest_io/default.nix
+#[path = "../../zkvms_host_io/src/sweep.rs"]
+#[allow(dead_code)]
+mod sweep;
+use sweep::Sweep;
+
 /// A CLI tool for running and benchmarking a guest program inside all
 /// supported zkVMs.
 /// This binary has been built with a single guest program in mind.
@@ -36,13 +58,57 @@ struct Cli {
     /// Append the resultant output to the given file, instead of replacing it
     #[arg(short, long)]
     append: bool,
+
+    /// Format of the resultant output
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+
+    /// Stop each operation after the given time (like "90s" or "2h 30m").
+    /// Its metrics record a timeout status
+    #[arg(long, value_parser = humantime::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Benchmark once for every value of an input field, given as
+    /// FIELD=START..END:STEP or FIELD=VALUE,VALUE,... Arrays and strings are
+    /// resized to the value. The resultant output is a series of all
+    /// benchmarks
+    #[arg(long, value_parser = Sweep::parse)]
+    sweep: Option<Sweep>,
+
+    /// Generate the input with the guest's input generator, given as
+    /// size=N, instead of reading it from files
+    #[arg(long, value_name = "size=N", value_parser = sweep::parse_generate, conflicts_with_all = ["private_input", "public_input"])]
+    generate: Option<usize>,
+
+    /// Seed of the input generator [default: 0]
+    #[arg(long, requires = "generate")]
+    seed: Option<u64>,
+
+    /// Use one of the guest's named inputs, instead of its default input
+    #[arg(long, conflicts_with_all = ["private_input", "public_input", "generate"])]
+    profile: Option<String>,
+
+    /// Set an input field to a value (in TOML format), overriding the input
+    /// files, profile or generated input. Can be given multiple times
+    #[arg(long, value_name = "KEY=VALUE")]
+    input: Vec<String>,
+
+    /// Print the JSON Schema of the resultant output and exit
+    #[arg(long)]
+    print_schema: bool,
 }
 
 static COMMAND_LOG_PATH: &str = "/tmp/output.log";
 static METRICS_TEMP_OUTPUT_PATH: &str = "/tmp/current_metrics";
-static PROOF_SIZE_FILE_PATH: &str = "/tmp/proof_size";
-
-fn run_command(zkvm_guest_command: &str, operation: &str, private_input: &Option<String>, public_input: &Option<String>) -> Result<std::process::Output, Error> {
+static PROOF_TEMP_PATH: &str = "/tmp/current_proof";
+
+fn run_command(
+    zkvm_guest_command: &str,
+    operation: &str,
+    private_input: &Option<String>,
+    public_input: &Option<String>,
+    extra_args: &[&str],
+) -> Result<std::process::Output, Error> {
     let inputs = vec![private_input.clone(), public_input.clone()];
     let inputs = inputs.iter().flatten().collect::<Vec<_>>();
     Command::new("runexec")
@@ -50,6 +116,7 @@ fn run_command(zkvm_guest_command: &str, operation: &str, private_input: &Option
         .args([zkvm_guest_command, operation])
         .arg("--benchmark")
         .args(["--metrics-output", METRICS_TEMP_OUTPUT_PATH])
+        .args(extra_args)
         .args(inputs)
         .stdout(Stdio::piped())
         .output()
@@ -61,30 +128,236 @@ fn get_runexec_value(output: &String, name: &str, end: char) -> String {
     right_half[..right_half.find(end).unwrap()].to_string()
 }
 
+/// Result of running a single operation with a zkVM (or native) host
+enum Outcome {
+    /// Metrics output of the host, alongside the memory usage from runexec.
+    /// Their status tells whether the host succeeded, timed out or failed
+    Finished(Box<RunMetrics>),
+    /// runexec couldn't be started
+    CommandFailed,
+    /// runexec exited with a non-zero status code
+    RunexecFailed,
+}
+
+/// Runs and benchmarks a single operation of the given host with runexec
+fn run_operation(
+    zkvm_guest_command: &str,
+    operation: &str,
+    cli: &Cli,
+    extra_args: &[&str],
+) -> Outcome {
+    let timeout = cli
+        .timeout
+        .map(|timeout| humantime::format_duration(timeout).to_string());
+    let generate = cli.generate.map(|size| format!("size={size}"));
+    let seed = cli.seed.map(|seed| seed.to_string());
+    let mut args = extra_args.to_vec();
+    if let Some(timeout) = &timeout {
+        args.extend(["--timeout", timeout]);
+    }
+    if let Some(generate) = &generate {
+        args.extend(["--generate", generate]);
+    }
+    if let Some(seed) = &seed {
+        args.extend(["--seed", seed]);
+    }
+    if let Some(profile) = &cli.profile {
+        args.extend(["--profile", profile]);
+    }
+    for input in &cli.input {
+        args.extend(["--input", input]);
+    }
+
+    // Otherwise a host, which fails before writing its metrics, would be
+    // reported with the metrics of the previous operation
+    let _ = remove_file(METRICS_TEMP_OUTPUT_PATH);
+
+    let output = run_command(
+        zkvm_guest_command,
+        operation,
+        &cli.private_input,
+        &cli.public_input,
+        &args,
+    );
+
+    // Couldn't run runexec
+    if let Err(msg) = output {
+        println!("Failed to run command!");
+        println!("{msg}");
+        return Outcome::CommandFailed;
+    }
+
+    // runexec ran and therefore produced some output
+    let output = output.unwrap();
+
+    // runexec exited with non-zero status code
+    if !output.status.success() {
+        println!("Command failed!");
+    }
+
+    let stdout = String::from_utf8(output.stdout).expect("failed to convert stdout to String");
+    println!("{stdout}");
+
+    if !output.stderr.is_empty() {
+        print!(
+            "{}",
+            String::from_utf8(output.stderr).expect("failed to convert stderr to String")
+        );
+    }
+
+    // runexec ran but exited with non-zero status code
+    if !output.status.success() {
+        return Outcome::RunexecFailed;
+    }
+
+    let return_value = get_runexec_value(&stdout, "returnvalue", '\n');
+
+    // The guest program ran but exited with non-zero status code
+    if return_value != "0" {
+        if let Some(log) = read_to_string(COMMAND_LOG_PATH).ok() {
+            println!("Command log:");
+            println!("{log}");
+        }
+    }
+
+    // Hosts write their metrics even on timeout or error, unless they failed
+    // before benchmarking started
+    let mut metrics = match read_to_string(METRICS_TEMP_OUTPUT_PATH) {
+        Ok(raw_data) => serde_json::from_str::<RunMetrics>(&raw_data).unwrap_or_else(|e| {
+            panic!(
+                "Couldn't parse temporary metrics output file \"{METRICS_TEMP_OUTPUT_PATH}\": {e}"
+            )
+        }),
+        Err(_) if return_value != "0" => {
+            let code = return_value.parse::<i32>().unwrap_or(-1);
+            let walltime = get_runexec_value(&stdout, "walltime", 's')
+                .parse::<f64>()
+                .unwrap_or(0.0);
+            RunMetrics::failed(
+                ErrorInfo {
+                    kind: ErrorKind::from_exit_code(code),
+                    message: format!("exited with status code {return_value}"),
+                },
+                walltime,
+            )
+        }
+        Err(_) => {
+            panic!("Couldn't read temporary metrics output file \"{METRICS_TEMP_OUTPUT_PATH}\"!")
+        }
+    };
+    metrics.memory = Some(
+        get_runexec_value(&stdout, "memory", 'B')
+            .parse::<u64>()
+            .unwrap(),
+    );
+
+    match metrics.status {
+        Status::Success => {}
+        Status::Timeout => println!("Timed out!"),
+        Status::Error => println!("Failed!"),
+        Status::Unsupported => println!("Unsupported!"),
+    }
+    Outcome::Finished(Box::new(metrics))
+}
+
+/// Rows of the tabular formats, one for native execution and one for every
+/// operation of every zkVM
+fn table_rows(runs: &ProgramMetrics) -> Vec<Value> {
+    let to_value =
+        |metrics: &RunMetrics| serde_json::to_value(metrics).expect("failed to serialize metrics");
+    let mut rows = Vec::new();
+
+    if let Some(native) = &runs.native {
+        let mut row = to_value(native);
+        row["zkvmName"] = "native".into();
+        row["operation"] = "native".into();
+        rows.push(row);
+    }
+
+    for run in &runs.benchmarking {
+        for (operation, metrics, overhead) in [
+            ("execute", &run.execute, run.native_overhead.execute),
+            ("prove", &run.prove, run.native_overhead.prove),
+            ("verify", &run.verify, run.native_overhead.verify),
+        ] {
+            let Some(metrics) = metrics else {
+                continue;
+            };
+
+            let mut row = to_value(metrics);
+            row["zkvmName"] = run.zkvm_name.clone().into();
+            row["zkvmRev"] = run.zkvm_rev.clone().into();
+            row["programName"] = run.program_name.clone().into();
+            row["commit"] = run.commit.clone().into();
+            row["operation"] = operation.into();
+            row["nativeOverhead"] = overhead.into();
+            rows.push(row);
+        }
+    }
+
+    rows
+}
+
 fn main() {
     let cli = Cli::parse();
 
-    // This is set by zkvms_guest_io/default.nix
-    let zkvm_guest_commands: Vec<&str> = env!("PROGRAMS")
-        .split(',')
-        .filter(|x| !x.is_empty())
-        .collect();
-    let ignored = cli.ignore.unwrap_or(Vec::new());
-
-    let mut runs = object! {
-        "benchmarking": [],
-        "hardware": {
-            cpu: [],
-            memory: {
-                model: Null,
-                size: 0,
-                speed: Null,
-            },
-            hardwareAcceleration: [],
-            accelerated: false
-        },
+    if cli.print_schema {
+        if cli.sweep.is_some() {
+            println!("{}", metrics::schema::<SweepMetrics<ProgramMetrics>>());
+        } else {
+            println!("{}", metrics::schema::<ProgramMetrics>());
+        }
+        return;
+    }
+
+    let hardware = hardware();
+
+    let Some(sweep) = &cli.sweep else {
+        let runs = benchmark(&cli, hardware, &[]);
+        format::write_metrics(
+            cli.format,
+            &cli.metrics_output,
+            cli.append,
+            &serde_json::to_value(&runs).expect("failed to serialize metrics"),
+            &table_rows(&runs),
+        );
+        return;
     };
 
+    let mut series = SweepMetrics {
+        schema_version: SCHEMA_VERSION,
+        field: sweep.field.clone(),
+        points: Vec::new(),
+    };
+    let mut rows = Vec::new();
+    for (value, point) in sweep.values.iter().zip(sweep.points()) {
+        println!("==== {point} ====");
+
+        let runs = benchmark(&cli, hardware.clone(), &["--sweep-point", &point]);
+        for mut row in table_rows(&runs) {
+            row["sweepField"] = sweep.field.clone().into();
+            row["sweepValue"] = (*value).into();
+            rows.push(row);
+        }
+        series.points.push(SweepPoint {
+            value: *value,
+            metrics: runs,
+        });
+    }
+
+    format::write_metrics(
+        cli.format,
+        &cli.metrics_output,
+        cli.append,
+        &serde_json::to_value(&series).expect("failed to serialize metrics"),
+        &rows,
+    );
+}
+
+/// Information about the hardware, on which the benchmarks run
+fn hardware() -> Hardware {
+    let mut hardware = Hardware::default();
+
     // Always available information
     let sys = System::new_all();
 
@@ -94,17 +367,15 @@ fn main() {
         .unique_by(|c| c.brand())
         .collect::<Vec<_>>();
     for cpu in cpus {
-        let mut hcpu = JsonValue::new_object();
-
-        hcpu["model"] = cpu.brand().into();
-        // This core count will be wrong in case the system has more than one CPUs
-        hcpu["cores"] = System::physical_core_count().unwrap_or(0).into();
-        hcpu["speed"] = cpu.frequency().into();
-
-        runs["hardware"]["cpu"].push(hcpu);
+        hardware.cpu.push(Cpu {
+            model: cpu.brand().to_string(),
+            // This core count will be wrong in case the system has more than one CPUs
+            cores: System::physical_core_count().unwrap_or(0) as u64,
+            speed: cpu.frequency(),
+        });
     }
 
-    runs["hardware"]["memory"]["size"] = sys.total_memory().into();
+    hardware.memory.size = sys.total_memory();
 
     // Available with root permissions
     // Note: it is not enough to just run the executable with sudo. runexec connects
@@ -115,16 +386,16 @@ fn main() {
         let cpus = sys
             .filter(|cpu: &SMBiosProcessorInformation| true)
             .collect::<Vec<SMBiosProcessorInformation>>();
-        for mut hcpu in runs["hardware"]["cpu"].members_mut() {
+        for hcpu in hardware.cpu.iter_mut() {
             if let Some(cpu) = cpus.iter().find(|cpu| {
                 if let Some(ver) = cpu.processor_version().ok() {
-                    ver.trim() == hcpu["model"].to_string().trim()
+                    ver.trim() == hcpu.model.trim()
                 } else {
                     false
                 }
             }) {
                 if let Some(CoreCount::Count(cores)) = cpu.core_count() {
-                    hcpu["cores"] = cores.into();
+                    hcpu.cores = cores.into();
                 }
             }
         }
@@ -132,14 +403,45 @@ fn main() {
         // Add memory model and speed
         if let Some(memory) = sys.find_map(|memory: SMBiosMemoryDevice| Some(memory)) {
             if let Some(model) = memory.part_number().ok() {
-                runs["hardware"]["memory"]["model"] = model.trim().into();
+                hardware.memory.model = Some(model.trim().to_string());
             }
             if let Some(MemorySpeed::MTs(speed)) = memory.speed() {
-                runs["hardware"]["memory"]["speed"] = speed.into();
+                hardware.memory.speed = Some(speed.into());
             }
         }
     }
 
+    hardware
+}
+
+/// Benchmarks the guest program with native execution and every zkVM.
+/// `extra_args` are passed to every host
+fn benchmark(cli: &Cli, hardware: Hardware, extra_args: &[&str]) -> ProgramMetrics {
+    // This is set by zkvms_guest_io/default.nix
+    let zkvm_guest_commands: Vec<&str> = env!("PROGRAMS")
+        .split(',')
+        .filter(|x| !x.is_empty())
+        .collect();
+    let ignored = cli.ignore.clone().unwrap_or_default();
+
+    let mut runs = ProgramMetrics {
+        schema_version: SCHEMA_VERSION,
+        benchmarking: Vec::new(),
+        native: None,
+        hardware,
+    };
+
+    // Native execution is the baseline for the zkVMs' overhead
+    if !ignored.iter().any(|i| "native".contains(i)) {
+        println!("== native ==");
+
+        // This is set by zkvms_guest_io/default.nix
+        if let Outcome::Finished(metrics) = run_operation(env!("NATIVE"), "native", cli, extra_args)
+        {
+            runs.native = Some(*metrics);
+        }
+    }
+
     'guest_iter: for zkvm_info in zkvm_guest_commands.into_iter() {
         let zkvm_info_fields: Vec<&str> = zkvm_info.split('|').collect();
         let zkvm = zkvm_info_fields[0];
@@ -148,104 +450,93 @@ fn main() {
             continue;
         }
 
-        let zkvmRev = zkvm_info_fields[1];
-        let guest = zkvm_info_fields[2];
-        let commit = zkvm_info_fields[3];
         let zkvm_guest_command = zkvm_info_fields[4];
 
-        let mut run = JsonValue::new_object();
-        run["zkvmName"] = zkvm.into();
-        run["zkvmRev"] = zkvmRev.into();
-        run["programName"] = guest.into();
-        run["commit"] = commit.into();
-
-        for operation in ["execute", "prove", "verify"] {
-            println!("== {operation} {zkvm} ==");
-
-            let output = run_command(zkvm_guest_command, operation, &cli.private_input, &cli.public_input);
+        let mut run = Benchmark {
+            zkvm_name: zkvm.to_string(),
+            zkvm_rev: zkvm_info_fields[1].to_string(),
+            program_name: zkvm_info_fields[2].to_string(),
+            commit: zkvm_info_fields[3].to_string(),
+            execute: None,
+            prove: None,
+            verify: None,
+            native_overhead: NativeOverhead::default(),
+        };
 
-            // Couldn't run runexec
-            if let Err(msg) = output {
-                println!("Failed to run command!");
-                println!("{msg}");
+        // A broken host fails all of its operations, without stopping the
+        // others
+        let capabilities = match HostCapabilities::query(zkvm_guest_command) {
+            Ok(capabilities) => capabilities,
+            Err(message) => {
+                println!("{message}!");
+                for operation in ["execute", "prove", "verify"] {
+                    let error = ErrorInfo {
+                        kind: ErrorKind::Other,
+                        message: message.clone(),
+                    };
+                    *run.operation_mut(operation) = Some(RunMetrics::failed(error, 0.0));
+                }
+                runs.benchmarking.push(run);
                 if cli.fail_propagation {
                     break 'guest_iter;
                 }
                 continue;
             }
+        };
 
-            // runexec ran and therefore produced some output
-            let output = output.unwrap();
-
-            // runexec exited with non-zero status code
-            if !output.status.success() {
-                println!("Command failed!");
-            }
-
-            let stdout =
-                String::from_utf8(output.stdout).expect("failed to convert stdout to String");
-            println!("{stdout}");
+        // Verification reuses the proof from the prove operation, if one was
+        // written, so it doesn't have to generate it again
+        let _ = remove_file(PROOF_TEMP_PATH);
 
-            if !output.stderr.is_empty() {
-                print!(
-                    "{}",
-                    String::from_utf8(output.stderr).expect("failed to convert stderr to String")
-                );
-            }
+        for operation in ["execute", "prove", "verify"] {
+            println!("== {operation} {zkvm} ==");
 
-            // runexec ran but exited with non-zero status code
-            if !output.status.success() {
-                break 'guest_iter;
+            // Unsupported operations are skipped, instead of letting the host
+            // fail
+            if !capabilities.supports(operation) {
+                println!("{zkvm} doesn't support {operation}!");
+                *run.operation_mut(operation) = Some(RunMetrics::unsupported(format!(
+                    "{zkvm} doesn't support {operation}"
+                )));
+                continue;
             }
 
-            // The guest program ran but exited with non-zero status code
-            if get_runexec_value(&stdout, "returnvalue", '\n') != "0" {
-                run[operation] = Null;
-                if let Some(log) = read_to_string(COMMAND_LOG_PATH).ok() {
-                    println!("Command log:");
-                    println!("{log}");
+            let proof_args: &[&str] = match operation {
+                "prove" if capabilities.proof_export => &["--proof-out", PROOF_TEMP_PATH],
+                "verify" if capabilities.proof_import && Path::new(PROOF_TEMP_PATH).exists() => {
+                    &["--proof-in", PROOF_TEMP_PATH]
                 }
-                continue;
+                _ => &[],
+            };
+
+            let args = [extra_args, proof_args].concat();
+            match run_operation(zkvm_guest_command, operation, cli, &args) {
+                Outcome::Finished(metrics) => *run.operation_mut(operation) = Some(*metrics),
+                Outcome::CommandFailed if cli.fail_propagation => break 'guest_iter,
+                Outcome::CommandFailed => continue,
+                Outcome::RunexecFailed => break 'guest_iter,
             }
-
-            let raw_data = &read_to_string(METRICS_TEMP_OUTPUT_PATH)
-                .ok()
-                .expect(&format!(
-                    "Couldn't read temporary metrics output file \"{METRICS_TEMP_OUTPUT_PATH}\"!"
-                ));
-            run[operation] = json::parse(raw_data).unwrap();
-            run[operation]["memory"] = get_runexec_value(&stdout, "memory", 'B')
-                .parse::<u64>()
-                .unwrap()
-                .into();
-
-            let proofSize = &read_to_string(PROOF_SIZE_FILE_PATH).ok().expect(&format!(
-                "Couldn't read temporary proof size file \"{PROOF_SIZE_FILE_PATH}\"!"
-            ));
-            run[operation]["proofSize"] = proofSize.parse::<u64>().unwrap().into();
         }
 
-        runs["benchmarking"].push(run);
-    }
-
-    if let Some(path) = cli.metrics_output {
-        let mut outfile = match OpenOptions::new()
-            .write(true)
-            .create(true)
-            .append(cli.append)
-            .truncate(!cli.append)
-            .open(&path)
-        {
-            Ok(file) => file,
-            Err(e) => {
-                panic!("Failed to open metrics output file \"{path}\": {e}");
-            }
+        // How many times slower than native execution each operation is
+        let native_mean = runs.native.as_ref().and_then(|n| n.mean);
+        let overhead = |metrics: &Option<RunMetrics>| match (metrics, native_mean) {
+            (
+                Some(RunMetrics {
+                    mean: Some(zkvm), ..
+                }),
+                Some(native),
+            ) if native > 0.0 => Some(zkvm / native),
+            _ => None,
+        };
+        run.native_overhead = NativeOverhead {
+            execute: overhead(&run.execute),
+            prove: overhead(&run.prove),
+            verify: overhead(&run.verify),
         };
 
-        if let Err(e) = writeln!(outfile, "{}", runs.dump()) {
-            panic!("Failed to write to metrics output file \"{path}\": {e}");
-        }
-    } else {
-        println!("{}", runs.dump());
+        runs.benchmarking.push(run);
     }
+
+    runs
 }
@@ -30,6 +30,12 @@ static DERIVES: &str = "#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deseria
 ///
 /// pub type Return = ...;
 ///
+/// // Extracts the Return value from an Output
+/// pub fn output_return(output: Output) -> Return {
+///     let (_, _, ..., ret) = output;
+///     ret
+/// }
+///
 /// #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// pub struct PublicInput {
 ///     pub ...: ...,
@@ -37,6 +43,13 @@ static DERIVES: &str = "#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deseria
 ///     ...
 /// }
 ///
+/// // Builds the Output from the public inputs and a return value
+/// impl PublicInput {
+///     pub fn with_return(&self, ret: Return) -> Output {
+///         (self...., self...., ..., ret)
+///     }
+/// }
+///
 /// #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// pub struct PrivateInput {
 ///     pub ...: ...,
@@ -61,12 +74,23 @@ static DERIVES: &str = "#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deseria
 ///         )
 ///     }
 /// }
+///
+/// // Converts a tuple to Input
+/// impl From<(...)> for Input {
+///     fn from((..., ..., ...): (...)) -> Input {
+///         Input { ..., ..., ... }
+///     }
+/// }
 /// ```
 #[proc_macro]
 pub fn generate_output_type_input_struct(_: TokenStream) -> TokenStream {
     let fd = new_fd();
 
-    let sep = if fd.types().is_empty() { "" } else { ", " };
+    let sep = if fd.public_types().is_empty() {
+        ""
+    } else {
+        ", "
+    };
     let output_type = format!(
         "pub type Output = ({} {} {});",
         fd.grouped_public_types(),
@@ -75,15 +99,44 @@ pub fn generate_output_type_input_struct(_: TokenStream) -> TokenStream {
     )
     .to_string();
 
-    let return_type = format!("pub type Return = {};", fd.return_type).to_string();
+    let ignored_public = fd
+        .public_patterns()
+        .iter()
+        .map(|_| "_,")
+        .collect::<String>();
+    let return_type = format!(
+        "
+        pub type Return = {};
+        pub fn output_return(output: Output) -> Return {{
+            let ({ignored_public} ret) = output;
+            ret
+        }}
+    ",
+        fd.return_type
+    )
+    .to_string();
 
     let public_attrs = fd
         .public_arguments()
         .iter()
         .map(|x| format!("pub {x},"))
         .collect::<String>();
-    let public_input_type =
-        format!("{} pub struct PublicInput {{ {} }}", DERIVES, public_attrs).to_string();
+    let public_values = fd
+        .public_patterns()
+        .iter()
+        .map(|x| format!("self.{x}.clone(),"))
+        .collect::<String>();
+    let public_input_type = format!(
+        "
+        {DERIVES} pub struct PublicInput {{ {public_attrs} }}
+        impl PublicInput {{
+            pub fn with_return(&self, ret: Return) -> Output {{
+                ({public_values} ret)
+            }}
+        }}
+    "
+    )
+    .to_string();
 
     let private_attrs = fd
         .private_arguments()
@@ -108,6 +161,7 @@ pub fn generate_output_type_input_struct(_: TokenStream) -> TokenStream {
         .map(|x| format!("input.{x},"))
         .collect::<String>();
     let types = fd.grouped_types();
+    let patterns = fd.grouped_patterns();
     let struct_def = &format!(
         "
         {DERIVES} pub struct Input {{
@@ -118,6 +172,11 @@ pub fn generate_output_type_input_struct(_: TokenStream) -> TokenStream {
                 ({convertion})
             }}
         }}
+        impl From<({types})> for Input {{
+            fn from(({patterns}): ({types})) -> Input {{
+                Input {{ {patterns} }}
+            }}
+        }}
     "
     )
     .to_string();
@@ -127,6 +186,190 @@ pub fn generate_output_type_input_struct(_: TokenStream) -> TokenStream {
         .unwrap()
 }
 
+/// Creates an array expression of `InputField`s, one for every argument of
+/// the guest's entrypoint function, in order.
+///
+/// # Usage
+///
+/// Inside zkvms_host_io:
+///
+/// ```rust
+/// static INPUT_FIELDS: &[InputField] = input_macros::input_fields!();
+/// ```
+///
+/// # Example output
+///
+/// ```rust
+/// &[
+///     InputField { name: "...", type_name: "...", public: ... },
+///     ...
+/// ]
+/// ```
+#[proc_macro]
+pub fn input_fields(_: TokenStream) -> TokenStream {
+    let fd = new_fd();
+    let public = fd
+        .public_patterns()
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>();
+
+    let fields = fd
+        .patterns()
+        .iter()
+        .zip(fd.types())
+        .map(|(pattern, ty)| {
+            let name = pattern.to_string();
+            // Token streams are printed with spaces between all tokens
+            let type_name = ty
+                .to_string()
+                .replace(' ', "")
+                .replace(',', ", ")
+                .replace(';', "; ");
+            format!(
+                "InputField {{ name: {name:?}, type_name: {type_name:?}, public: {} }},",
+                public.contains(&name)
+            )
+        })
+        .collect::<String>();
+
+    format!("&[{fields}]").parse().unwrap()
+}
+
+/// Creates an `Option<&str>` expression with the contents of the guest's
+/// `expected_output.toml`, or `None` when the guest doesn't have one.
+///
+/// # Usage
+///
+/// Inside zkvms_host_io:
+///
+/// ```rust
+/// static EXPECTED_OUTPUT: Option<&str> = input_macros::expected_output!();
+/// ```
+#[proc_macro]
+pub fn expected_output(_: TokenStream) -> TokenStream {
+    let path = format!(
+        "{}/expected_output.toml",
+        std::env::var("INPUTS_DIR").expect("INPUTS_DIR is missing")
+    );
+    if std::path::Path::new(&path).exists() {
+        format!("Some(include_str!({path:?}))")
+    } else {
+        "None".to_string()
+    }
+    .parse()
+    .unwrap()
+}
+
+/// Creates an array expression of `Profile`s, one for every directory in
+/// the guest's `inputs` directory, ordered by name.
+///
+/// A profile directory holds a `public_input.toml` and `private_input.toml`
+/// (either can be left out, when empty) and optionally an
+/// `expected_output.toml`, the same as the guest's default files.
+///
+/// # Usage
+///
+/// Inside zkvms_host_io:
+///
+/// ```rust
+/// static PROFILES: &[Profile] = input_macros::input_profiles!();
+/// ```
+///
+/// # Example output
+///
+/// ```rust
+/// &[
+///     Profile { name: "...", public_input: include_str!("..."), private_input: "", expected_output: None },
+///     ...
+/// ]
+/// ```
+#[proc_macro]
+pub fn input_profiles(_: TokenStream) -> TokenStream {
+    let dir = format!(
+        "{}/inputs",
+        std::env::var("INPUTS_DIR").expect("INPUTS_DIR is missing")
+    );
+    let mut names = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .map(|entry| entry.expect("failed to read the inputs directory"))
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().into_string().unwrap())
+            .collect::<Vec<String>>(),
+        Err(_) => Vec::new(),
+    };
+    names.sort();
+
+    let include = |name: &str, file: &str| {
+        let path = format!("{dir}/{name}/{file}");
+        std::path::Path::new(&path)
+            .exists()
+            .then(|| format!("include_str!({path:?})"))
+    };
+    let profiles = names
+        .iter()
+        .map(|name| {
+            if name == "default" {
+                panic!("Input profile \"default\" is reserved for the guest's default input!");
+            }
+            let public_input = include(name, "public_input.toml");
+            let private_input = include(name, "private_input.toml");
+            let expected_output = include(name, "expected_output.toml");
+            format!(
+                "Profile {{ name: {name:?}, public_input: {}, private_input: {}, expected_output: {} }},",
+                public_input.unwrap_or("\"\"".to_string()),
+                private_input.unwrap_or("\"\"".to_string()),
+                expected_output.map_or("None".to_string(), |x| format!("Some({x})")),
+            )
+        })
+        .collect::<String>();
+
+    format!("&[{profiles}]").parse().unwrap()
+}
+
+/// Creates an `Option<Generator>` expression with the guest's input
+/// generator, or `None` when the guest doesn't have one.
+///
+/// The generator is noted in `generator.txt` under `INPUTS_DIR` by the
+/// `#[guests_macro::input_generator]` attribute and is called through the
+/// guest crate, which has to be a dependency of the host, named `zkp`.
+///
+/// # Usage
+///
+/// Inside a host:
+///
+/// ```rust
+/// let run_info = read_args::<...>(input_generator!());
+/// ```
+///
+/// # Example output
+///
+/// ```rust
+/// Some((|size, seed| zkp::...(size, seed).into()) as zkvms_host_io::Generator)
+/// ```
+#[proc_macro]
+pub fn input_generator(_: TokenStream) -> TokenStream {
+    let path = format!(
+        "{}/generator.txt",
+        std::env::var("INPUTS_DIR").expect("INPUTS_DIR is missing")
+    );
+    let generator = match std::fs::read_to_string(&path) {
+        Ok(generator) if !generator.trim().is_empty() => {
+            let fd = FunctionDefinition::new(&generator.parse().unwrap());
+            format!(
+                "Some((|size, seed| zkp::{}(size, seed).into()) as zkvms_host_io::Generator)",
+                fd.name
+            )
+        }
+        Ok(_) => "None".to_string(),
+        Err(_) => return "None".parse().unwrap(),
+    };
+    // Including the note makes cargo rebuild the host when it changes
+    format!("{{ const _: &str = include_str!({path:?}); {generator} }}")
+        .parse()
+        .unwrap()
+}
+
 /// Repeats the given item as many times as fields there are, while replacing
 /// all `.yield` occurences with the fields value (field name).
 fn foreach_field(item: TokenStream, fields: Vec<TokenStream>) -> TokenStream {
@@ -166,31 +409,31 @@ pub fn foreach_private_input_field(item: TokenStream) -> TokenStream {
 /// Assuming the `run_info` variable is present, it creates a block with all
 /// needed code to properly benchmark the input code, according to all command
 /// parameters.
+///
+/// The first `run_info.warmup` iterations are not measured. Phases, timed
+/// inside of the block, are recorded only for measured iterations.
 #[proc_macro]
 pub fn benchmarkable(item: TokenStream) -> TokenStream {
     format!(
         r#"
         {{
-             use std::time::Instant;
+             use zkvms_host_io::Recording;
 
-             let mut starts = Vec::new();
-             let mut ends = Vec::new();
+             for i in 1..=(run_info.warmup + run_info.runs) {{
+                 let measured = run_info.benchmarking && i > run_info.warmup;
+                 zkvms_host_io::record_phases(if measured {{ Recording::Measured }} else {{ Recording::Warmup }});
 
-             for i in 1..=run_info.runs {{
-                 if run_info.benchmarking {{
-                     starts.push(Instant::now());
+                 if measured {{
+                     zkvms_host_io::start_iteration();
                  }}
 
                  {item}
 
-                 if run_info.benchmarking {{
-                     ends.push(Instant::now());
+                 if measured {{
+                     zkvms_host_io::end_iteration();
                  }}
              }}
-
-             if run_info.benchmarking {{
-                 zkvms_host_io::emit_benchmark_results(run_info, starts, ends);
-             }}
+             zkvms_host_io::record_phases(Recording::OneTime);
         }}
     "#
     )
@@ -0,0 +1,52 @@
+//! Declaration of what a zkVM host supports.
+
+use crate::{metrics::HostCapabilities, RunType, ZKVM, ZKVM_REV};
+use clap::ValueEnum;
+
+/// Operations and features, which a zkVM host supports.
+///
+/// Each host implements this for its own backend (marker) type. `read_args`
+/// rejects unsupported operations with an unsupported error, and
+/// `--capabilities` prints the declaration, so the orchestrator can skip them
+/// without running the host.
+pub trait Capabilities {
+    /// Operations the host can run
+    const RUN_TYPES: &'static [RunType];
+
+    /// Kinds of proofs the host can generate
+    const PROOF_MODES: &'static [&'static str];
+
+    /// Operations for which the host reports cycle counts
+    const CYCLE_COUNTING: &'static [RunType];
+
+    /// Operations which check the value, returned by the guest, against the
+    /// expected output
+    const OUTPUT_CHECKING: &'static [RunType];
+
+    /// Whether the generated proof can be written to a file with `--proof-out`
+    const PROOF_EXPORT: bool;
+
+    /// Whether a proof can be verified from a file with `--proof-in`
+    const PROOF_IMPORT: bool;
+}
+
+fn names(run_types: &[RunType]) -> Vec<String> {
+    run_types
+        .iter()
+        .map(|run_type| run_type.to_possible_value().unwrap().get_name().to_string())
+        .collect()
+}
+
+/// The `--capabilities` output of the given host
+pub fn capabilities<B: Capabilities>() -> HostCapabilities {
+    HostCapabilities {
+        zkvm_name: ZKVM.to_string(),
+        zkvm_rev: ZKVM_REV.to_string(),
+        operations: names(B::RUN_TYPES),
+        proof_modes: B::PROOF_MODES.iter().map(|x| x.to_string()).collect(),
+        cycle_counting: names(B::CYCLE_COUNTING),
+        output_checking: names(B::OUTPUT_CHECKING),
+        proof_export: B::PROOF_EXPORT,
+        proof_import: B::PROOF_IMPORT,
+    }
+}
@@ -0,0 +1,59 @@
+//! Child processes of a host, like the zkwasm-cli commands of zkWasm.
+//!
+//! They don't stop when the host exits, so on timeout they're killed
+//! explicitly, instead of running on after the benchmark has moved on.
+
+use std::{
+    io,
+    process::{Child, Command, ExitStatus, Output},
+    sync::Mutex,
+};
+
+/// IDs of the running child processes
+static CHILDREN: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// Runs the command to completion, like `Command::status`, while tracking
+/// it, so it's killed when the host times out.
+pub fn run_child(command: &mut Command) -> io::Result<ExitStatus> {
+    let mut child = spawn(command)?;
+    let status = child.wait();
+    untrack(child.id());
+    status
+}
+
+/// Like `run_child`, but collects the output, which isn't redirected
+/// elsewhere (with `Command::stdout` or `Command::stderr`).
+pub fn run_child_output(command: &mut Command) -> io::Result<Output> {
+    let child = spawn(command)?;
+    let id = child.id();
+    let output = child.wait_with_output();
+    untrack(id);
+    output
+}
+
+fn spawn(command: &mut Command) -> io::Result<Child> {
+    // Spawning while holding the lock means a timeout either happens
+    // before the child exists or sees it
+    let mut children = CHILDREN.lock().unwrap();
+    let child = command.spawn()?;
+    children.push(child.id());
+    Ok(child)
+}
+
+fn untrack(id: u32) {
+    CHILDREN.lock().unwrap().retain(|&x| x != id);
+}
+
+/// Kills all running child processes. No other child can be started
+/// afterwards, since the host is about to exit.
+pub(crate) fn kill_children() {
+    let children = CHILDREN.lock().unwrap();
+    for &id in children.iter() {
+        // The child might have exited in the meantime, so failure is fine
+        unsafe {
+            libc::kill(id as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    // Keep the lock until the process exits
+    std::mem::forget(children);
+}
@@ -0,0 +1,311 @@
+//! Typed configuration knobs of zkVM hosts.
+//!
+//! The value of every knob is taken from the first one of: `--set KEY=VALUE`,
+//! the environment, the guest's `default.env` and the knob's default.
+
+use crate::metrics::{ConfigSource, ConfigValue};
+use std::{collections::*, env, fmt, str::FromStr};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KnobType {
+    /// Non-negative whole number
+    Integer,
+    /// `true` or `false`
+    Boolean,
+    /// Any string
+    String,
+    /// Path to a file or directory
+    Path,
+    /// One of the given strings
+    Choice(&'static [&'static str]),
+}
+
+impl KnobType {
+    /// Checks whether the value can be parsed as this type
+    fn validate(&self, value: &str) -> Result<(), String> {
+        let valid = match self {
+            KnobType::Integer => value.parse::<u64>().is_ok(),
+            KnobType::Boolean => value.parse::<bool>().is_ok(),
+            KnobType::String | KnobType::Path => true,
+            KnobType::Choice(choices) => choices.contains(&value),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("\"{value}\" isn't a valid {self}"))
+        }
+    }
+}
+
+impl fmt::Display for KnobType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KnobType::Integer => write!(f, "integer"),
+            KnobType::Boolean => write!(f, "boolean"),
+            KnobType::String => write!(f, "string"),
+            KnobType::Path => write!(f, "path"),
+            KnobType::Choice(choices) => write!(f, "one of {}", choices.join(", ")),
+        }
+    }
+}
+
+/// A configuration variable of a zkVM host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Knob {
+    pub name: &'static str,
+    pub kind: KnobType,
+    /// Value, used when the knob isn't set anywhere
+    pub default: &'static str,
+    pub description: &'static str,
+}
+
+/// Configuration knobs of a zkVM host.
+///
+/// Each host implements this for its own backend (marker) type, so
+/// `read_args` can validate the knobs' values and reject unknown ones given
+/// with `--set`.
+pub trait Configurable {
+    const KNOBS: &'static [Knob];
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigSource::Cli => write!(f, "--set"),
+            ConfigSource::Environment => write!(f, "environment"),
+            ConfigSource::DefaultEnv => write!(f, "default.env"),
+            ConfigSource::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Values of all knobs of a host
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Config {
+    knobs: &'static [Knob],
+    settings: BTreeMap<&'static str, ConfigValue>,
+}
+
+impl Config {
+    /// Takes the value of every knob from the first source which sets it,
+    /// and validates it.
+    ///
+    /// Fails when a value is invalid or when `--set` is used with an unknown
+    /// knob. `default_env` is shared by all zkVMs, so it may contain unknown
+    /// knobs.
+    pub fn resolve(
+        knobs: &'static [Knob],
+        cli: &[(String, String)],
+        default_env: &HashMap<String, String>,
+    ) -> Result<Config, String> {
+        if let Some((key, _)) = cli
+            .iter()
+            .find(|(key, _)| !knobs.iter().any(|knob| knob.name == key))
+        {
+            let known = knobs.iter().map(|knob| knob.name).collect::<Vec<_>>();
+            return Err(if known.is_empty() {
+                format!("unknown configuration knob \"{key}\", this host has none")
+            } else {
+                format!(
+                    "unknown configuration knob \"{key}\", expected one of: {}",
+                    known.join(", ")
+                )
+            });
+        }
+
+        let mut settings = BTreeMap::new();
+        for knob in knobs {
+            let (value, source) =
+                if let Some((_, value)) = cli.iter().rev().find(|(key, _)| key == knob.name) {
+                    (value.clone(), ConfigSource::Cli)
+                } else if let Ok(value) = env::var(knob.name) {
+                    (value, ConfigSource::Environment)
+                } else if let Some(value) = default_env.get(knob.name) {
+                    (value.clone(), ConfigSource::DefaultEnv)
+                } else {
+                    (knob.default.to_string(), ConfigSource::Default)
+                };
+
+            knob.kind
+                .validate(&value)
+                .map_err(|e| format!("{} (from {source}): {e}", knob.name))?;
+            settings.insert(knob.name, ConfigValue { value, source });
+        }
+
+        Ok(Config { knobs, settings })
+    }
+
+    /// Value and source of the knob with the given name
+    pub fn setting(&self, name: &str) -> &ConfigValue {
+        self.settings
+            .get(name)
+            .unwrap_or_else(|| panic!("Unknown configuration knob \"{name}\"!"))
+    }
+
+    /// Value of the knob with the given name, parsed as `T`
+    pub fn get<T: FromStr>(&self, name: &str) -> T {
+        let value = &self.setting(name).value;
+        value
+            .parse::<T>()
+            .unwrap_or_else(|_| panic!("Configuration knob \"{name}\" has an invalid value!"))
+    }
+
+    /// Every knob alongside its value, in the order of registration
+    pub fn iter(&self) -> impl Iterator<Item = (&'static Knob, &ConfigValue)> + '_ {
+        self.knobs
+            .iter()
+            .map(|knob| (knob, &self.settings[knob.name]))
+    }
+
+    /// Values of all knobs by name, as recorded in the metrics
+    pub fn values(&self) -> BTreeMap<String, ConfigValue> {
+        self.settings
+            .iter()
+            .map(|(name, setting)| (name.to_string(), setting.clone()))
+            .collect()
+    }
+}
+
+/// Parses a `KEY=VALUE` argument of `--set`
+pub(crate) fn parse_key_value(argument: &str) -> Result<(String, String), String> {
+    argument
+        .split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected KEY=VALUE, found \"{argument}\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names are unique to this module, since tests share the environment
+    static KNOBS: &[Knob] = &[
+        Knob {
+            name: "CONFIG_TEST_SIZE",
+            kind: KnobType::Integer,
+            default: "8",
+            description: "",
+        },
+        Knob {
+            name: "CONFIG_TEST_SCHEME",
+            kind: KnobType::Choice(&["a", "b"]),
+            default: "a",
+            description: "",
+        },
+    ];
+
+    fn set(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn setting(value: &str, source: ConfigSource) -> ConfigValue {
+        ConfigValue {
+            value: value.to_string(),
+            source,
+        }
+    }
+
+    #[test]
+    fn layers() {
+        let default_env = HashMap::from([
+            ("CONFIG_TEST_SIZE".to_string(), "16".to_string()),
+            ("OTHER_ZKVM_KNOB".to_string(), "1".to_string()),
+        ]);
+
+        let config = Config::resolve(KNOBS, &[], &default_env).unwrap();
+        assert_eq!(
+            config.setting("CONFIG_TEST_SIZE"),
+            &setting("16", ConfigSource::DefaultEnv)
+        );
+        assert_eq!(
+            config.setting("CONFIG_TEST_SCHEME"),
+            &setting("a", ConfigSource::Default)
+        );
+        assert_eq!(config.get::<u32>("CONFIG_TEST_SIZE"), 16);
+
+        // Later --set arguments win
+        let cli = set(&[("CONFIG_TEST_SIZE", "32"), ("CONFIG_TEST_SIZE", "64")]);
+        let config = Config::resolve(KNOBS, &cli, &default_env).unwrap();
+        assert_eq!(
+            config.setting("CONFIG_TEST_SIZE"),
+            &setting("64", ConfigSource::Cli)
+        );
+        assert_eq!(
+            config.iter().map(|(knob, _)| knob.name).collect::<Vec<_>>(),
+            ["CONFIG_TEST_SIZE", "CONFIG_TEST_SCHEME"]
+        );
+    }
+
+    #[test]
+    fn environment_over_default_env() {
+        static KNOBS: &[Knob] = &[Knob {
+            name: "CONFIG_TEST_ENVIRONMENT",
+            kind: KnobType::Boolean,
+            default: "false",
+            description: "",
+        }];
+        env::set_var("CONFIG_TEST_ENVIRONMENT", "true");
+        let default_env =
+            HashMap::from([("CONFIG_TEST_ENVIRONMENT".to_string(), "false".to_string())]);
+
+        let config = Config::resolve(KNOBS, &[], &default_env).unwrap();
+        assert_eq!(
+            config.setting("CONFIG_TEST_ENVIRONMENT"),
+            &setting("true", ConfigSource::Environment)
+        );
+
+        let cli = set(&[("CONFIG_TEST_ENVIRONMENT", "false")]);
+        let config = Config::resolve(KNOBS, &cli, &default_env).unwrap();
+        assert_eq!(
+            config.setting("CONFIG_TEST_ENVIRONMENT"),
+            &setting("false", ConfigSource::Cli)
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        let cli = set(&[("CONFIG_TEST_SCHEME", "c")]);
+        assert_eq!(
+            Config::resolve(KNOBS, &cli, &HashMap::new()),
+            Err("CONFIG_TEST_SCHEME (from --set): \"c\" isn't a valid one of a, b".to_string())
+        );
+
+        let default_env = HashMap::from([("CONFIG_TEST_SIZE".to_string(), "-1".to_string())]);
+        assert_eq!(
+            Config::resolve(KNOBS, &[], &default_env),
+            Err("CONFIG_TEST_SIZE (from default.env): \"-1\" isn't a valid integer".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_knobs() {
+        let cli = set(&[("CONFIG_TEST_SIZE", "1"), ("K", "1")]);
+        assert_eq!(
+            Config::resolve(KNOBS, &cli, &HashMap::new()),
+            Err(
+                "unknown configuration knob \"K\", expected one of: CONFIG_TEST_SIZE, \
+                 CONFIG_TEST_SCHEME"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            Config::resolve(&[], &cli, &HashMap::new()),
+            Err("unknown configuration knob \"CONFIG_TEST_SIZE\", this host has none".to_string())
+        );
+    }
+
+    #[test]
+    fn key_values() {
+        assert_eq!(
+            parse_key_value("K=a=b"),
+            Ok(("K".to_string(), "a=b".to_string()))
+        );
+        assert_eq!(
+            parse_key_value("K"),
+            Err("expected KEY=VALUE, found \"K\"".to_string())
+        );
+    }
+}
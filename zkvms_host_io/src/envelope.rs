@@ -0,0 +1,225 @@
+//! A versioned, self-describing file format for storing proofs.
+//!
+//! Besides the proof, an envelope stores enough information to know what it
+//! proves and how to verify it: the zkVM, the guest program, its function
+//! signature, public input and output, and the verifying key.
+//!
+//! The file consists of:
+//!
+//! 1. `MAGIC` (8 bytes)
+//! 2. Format version (4 bytes, little endian)
+//! 3. Length of the header (8 bytes, little endian), followed by the JSON
+//!    encoded `EnvelopeHeader`
+//! 4. Length of the verifying key (8 bytes, little endian), followed by the
+//!    serialized key
+//! 5. Length of the proof (8 bytes, little endian), followed by the
+//!    serialized proof
+//!
+//! This module does not depend on the guest's types, so it can be shared
+//! with other crates via a [mod path
+//! attribute](https://doc.rust-lang.org/reference/items/modules.html#the-path-attribute).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{read, write};
+
+pub static MAGIC: &[u8; 8] = b"ZKVMPRF\0";
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvelopeHeader {
+    /// Name of zkVM, which generated the proof
+    pub zkvm_name: String,
+    /// Commit or tag on which the zkVM is built
+    pub zkvm_rev: String,
+    /// Name of the guest program
+    pub program_name: String,
+    /// Type of the guest's entrypoint function, as in `guests/type.txt`
+    pub signature: String,
+    /// Public input of the guest program
+    pub public_input: Value,
+    /// Decoded public output (public inputs and return value), null if the
+    /// zkVM doesn't expose it
+    pub output: Value,
+    /// Human readable digest of the verifying key or image ID, if any
+    pub key_digest: Option<String>,
+    /// Serialization format of the proof
+    pub proof_encoding: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    pub header: EnvelopeHeader,
+    pub key: Vec<u8>,
+    pub proof: Vec<u8>,
+}
+
+impl Envelope {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let header = serde_json::to_vec(&self.header).expect("failed to serialize header");
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        for section in [&header, &self.key, &self.proof] {
+            bytes.extend((section.len() as u64).to_le_bytes());
+            bytes.extend(section);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Envelope, String> {
+        let rest = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .ok_or("not a proof envelope")?;
+
+        let (version, mut rest) = rest.split_first_chunk::<4>().ok_or("truncated envelope")?;
+        let version = u32::from_le_bytes(*version);
+        if version != VERSION {
+            return Err(format!(
+                "unsupported envelope version {version}, expected {VERSION}"
+            ));
+        }
+
+        let header = Self::next_section(&mut rest)?;
+        let key = Self::next_section(&mut rest)?;
+        let proof = Self::next_section(&mut rest)?;
+        if !rest.is_empty() {
+            return Err(format!("{} unexpected bytes after the proof", rest.len()));
+        }
+
+        Ok(Envelope {
+            header: serde_json::from_slice(&header).map_err(|e| format!("invalid header: {e}"))?,
+            key,
+            proof,
+        })
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        write(path, self.to_bytes())
+            .map_err(|e| format!("couldn't write proof file \"{path}\": {e}"))
+    }
+
+    pub fn read(path: &str) -> Result<Envelope, String> {
+        let bytes = read(path).map_err(|e| format!("couldn't read proof file \"{path}\": {e}"))?;
+        Self::from_bytes(&bytes).map_err(|e| format!("couldn't parse proof file \"{path}\": {e}"))
+    }
+
+    /// Removes a length-prefixed section from the start of bytes
+    fn next_section(bytes: &mut &[u8]) -> Result<Vec<u8>, String> {
+        let (len, rest) = bytes.split_first_chunk::<8>().ok_or("truncated envelope")?;
+        let len = u64::from_le_bytes(*len) as usize;
+        if rest.len() < len {
+            return Err("truncated envelope".to_string());
+        }
+        let (section, rest) = rest.split_at(len);
+        *bytes = rest;
+        Ok(section.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn envelope() -> Envelope {
+        Envelope {
+            header: EnvelopeHeader {
+                zkvm_name: "sp1".to_string(),
+                zkvm_rev: "v4.0.0".to_string(),
+                program_name: "fibonacci".to_string(),
+                signature: "fn main(#[public] n: u8, #[private] fN: u64) -> bool".to_string(),
+                public_input: json!({ "n": 5 }),
+                output: json!([5, true]),
+                key_digest: Some("0x00ab".to_string()),
+                proof_encoding: "bincode".to_string(),
+            },
+            key: vec![1, 2, 3],
+            proof: vec![4; 100],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let envelope = envelope();
+        assert_eq!(Envelope::from_bytes(&envelope.to_bytes()), Ok(envelope));
+
+        let empty = Envelope {
+            key: Vec::new(),
+            proof: Vec::new(),
+            ..self::envelope()
+        };
+        assert_eq!(Envelope::from_bytes(&empty.to_bytes()), Ok(empty));
+    }
+
+    #[test]
+    fn truncated_envelopes() {
+        let bytes = envelope().to_bytes();
+        for len in MAGIC.len()..bytes.len() {
+            assert_eq!(
+                Envelope::from_bytes(&bytes[..len]),
+                Err("truncated envelope".to_string()),
+                "truncated to {len} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let mut bytes = envelope().to_bytes();
+        bytes.extend(b"xyz");
+        assert_eq!(
+            Envelope::from_bytes(&bytes),
+            Err("3 unexpected bytes after the proof".to_string())
+        );
+
+        let concatenated = [envelope().to_bytes(), envelope().to_bytes()].concat();
+        assert!(Envelope::from_bytes(&concatenated).is_err());
+    }
+
+    #[test]
+    fn oversized_section() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend(u64::MAX.to_le_bytes());
+        assert_eq!(
+            Envelope::from_bytes(&bytes),
+            Err("truncated envelope".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_envelopes() {
+        let bytes = envelope().to_bytes();
+
+        assert_eq!(
+            Envelope::from_bytes(&bytes[1..]),
+            Err("not a proof envelope".to_string())
+        );
+        assert_eq!(
+            Envelope::from_bytes(&bytes[..4]),
+            Err("not a proof envelope".to_string())
+        );
+
+        let mut newer = bytes.clone();
+        newer[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert_eq!(
+            Envelope::from_bytes(&newer),
+            Err(format!(
+                "unsupported envelope version {}, expected {VERSION}",
+                VERSION + 1
+            ))
+        );
+
+        let mut header = MAGIC.to_vec();
+        header.extend(VERSION.to_le_bytes());
+        for section in [b"{}".as_slice(), &[], &[]] {
+            header.extend((section.len() as u64).to_le_bytes());
+            header.extend(section);
+        }
+        assert!(Envelope::from_bytes(&header)
+            .unwrap_err()
+            .starts_with("invalid header: missing field"));
+    }
+}
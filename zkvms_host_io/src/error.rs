@@ -0,0 +1,73 @@
+//! Failures of a zkVM host.
+//!
+//! Hosts return a `HostError` instead of panicking, so the failure is
+//! reported in the metrics output and through a distinct exit code.
+
+use crate::metrics::{ErrorInfo, ErrorKind};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostError {
+    /// The zkVM doesn't support the requested operation
+    Unsupported(String),
+    /// The input (or proof) files couldn't be read or are malformed, or the
+    /// proof file couldn't be written
+    InvalidInput(String),
+    /// The guest program panicked or otherwise failed while running
+    GuestPanic(String),
+    /// The zkVM failed to generate a proof
+    Prover(String),
+    /// The proof was rejected by the verifier
+    VerificationFailed(String),
+    /// The guest returned a different value than the expected output
+    UnexpectedOutput(String),
+}
+
+impl HostError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            HostError::Unsupported(_) => ErrorKind::Unsupported,
+            HostError::InvalidInput(_) => ErrorKind::InvalidInput,
+            HostError::GuestPanic(_) => ErrorKind::GuestPanic,
+            HostError::Prover(_) => ErrorKind::ProverError,
+            HostError::VerificationFailed(_) => ErrorKind::VerificationFailed,
+            HostError::UnexpectedOutput(_) => ErrorKind::UnexpectedOutput,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            HostError::Unsupported(message)
+            | HostError::InvalidInput(message)
+            | HostError::GuestPanic(message)
+            | HostError::Prover(message)
+            | HostError::VerificationFailed(message)
+            | HostError::UnexpectedOutput(message) => message,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.kind().exit_code()
+    }
+
+    pub(crate) fn info(&self) -> ErrorInfo {
+        ErrorInfo {
+            kind: self.kind(),
+            message: self.message().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for HostError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            HostError::Unsupported(_) => "Unsupported operation",
+            HostError::InvalidInput(_) => "Invalid input",
+            HostError::GuestPanic(_) => "Guest program failed",
+            HostError::Prover(_) => "Failed to generate proof",
+            HostError::VerificationFailed(_) => "Failed to verify proof",
+            HostError::UnexpectedOutput(_) => "Guest returned an unexpected value",
+        };
+        write!(f, "{description}: {}", self.message())
+    }
+}
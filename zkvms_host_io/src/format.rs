@@ -0,0 +1,195 @@
+//! Formats in which metrics are written.
+//!
+//! This file is also included by zkvms_guest_io, so the zkVM hosts and the
+//! orchestrator produce the same column layout.
+
+use clap::ValueEnum;
+use serde_json::Value;
+use std::{
+    fs::{metadata, read_to_string, OpenOptions},
+    io::Write,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// A single JSON value. Appending collects all values into an array
+    Json,
+    /// One compact JSON value per line. Always appended to the file
+    Jsonl,
+    /// Comma-separated values, one row per operation
+    Csv,
+    /// Markdown table, one row per operation
+    Markdown,
+}
+
+/// Columns of the tabular formats. Nested fields are separated by dots.
+pub const COLUMNS: &[&str] = &[
+    "zkvmName",
+    "zkvmRev",
+    "programName",
+    "commit",
+    "operation",
+    "profile",
+    "sweepField",
+    "sweepValue",
+    "status",
+    "error.kind",
+    "error.message",
+    "timeStarted",
+    "runs",
+    "warmup",
+    "totalDuration",
+    "mean",
+    "deviation",
+    "min",
+    "max",
+    "median",
+    "p90",
+    "p99",
+    "cv",
+    "confidenceInterval.level",
+    "confidenceInterval.low",
+    "confidenceInterval.high",
+    "outliers",
+    "stable",
+    "phases.setup.mean",
+    "phases.execute.mean",
+    "phases.prove.mean",
+    "phases.compress.mean",
+    "phases.verify.mean",
+    "memory",
+    "userTime",
+    "systemTime",
+    "proofSize",
+    "proofEncoding",
+    "cycles",
+    "pagingCycles",
+    "segments",
+    "nativeOverhead",
+];
+
+/// Returns the (nested) field of the given column
+fn lookup<'a>(row: &'a Value, column: &str) -> &'a Value {
+    column.split('.').fold(row, |value, key| &value[key])
+}
+
+/// String representation of a single cell. Missing values are empty.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(string) => string.clone(),
+        _ => value.to_string(),
+    }
+}
+
+fn csv_cell(value: &Value) -> String {
+    let cell = cell(value);
+    if cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell
+    }
+}
+
+fn markdown_cell(value: &Value) -> String {
+    cell(value).replace('|', "\\|").replace('\n', " ")
+}
+
+/// Formats the given document and rows. The header of tabular formats is
+/// only included when `header` is set.
+///
+/// `document` is used by the JSON formats, while `rows` by the tabular ones.
+fn render(format: Format, document: &Value, rows: &[Value], header: bool) -> String {
+    let mut out = String::new();
+    match format {
+        // The alternate form is pretty printed
+        Format::Json => out = format!("{document:#}\n"),
+        Format::Jsonl => out = format!("{document}\n"),
+        Format::Csv => {
+            if header {
+                out += &(COLUMNS.join(",") + "\n");
+            }
+            for row in rows {
+                let cells = COLUMNS
+                    .iter()
+                    .map(|c| csv_cell(lookup(row, c)))
+                    .collect::<Vec<String>>();
+                out += &(cells.join(",") + "\n");
+            }
+        }
+        Format::Markdown => {
+            if header {
+                out += &format!("| {} |\n", COLUMNS.join(" | "));
+                out += &format!("|{}\n", " --- |".repeat(COLUMNS.len()));
+            }
+            for row in rows {
+                let cells = COLUMNS
+                    .iter()
+                    .map(|c| markdown_cell(lookup(row, c)))
+                    .collect::<Vec<String>>();
+                out += &format!("| {} |\n", cells.join(" | "));
+            }
+        }
+    }
+    out
+}
+
+/// Writes the metrics in the given format to a file of the given path, or
+/// to stdout if there is no path.
+///
+/// When appending to a file, the tabular formats only write a header if the
+/// file is empty, and `Json` turns the file's contents into an array of all
+/// written documents. `Jsonl` adds a line, even without `append`.
+pub fn write_metrics(
+    format: Format,
+    path: &Option<String>,
+    append: bool,
+    document: &Value,
+    rows: &[Value],
+) {
+    let Some(path) = path else {
+        print!("{}", render(format, document, rows, true));
+        return;
+    };
+
+    // A JSON file can only hold a single value, so appended documents are
+    // collected into an array
+    let (contents, append) = match format {
+        Format::Json if append => match read_to_string(path) {
+            Ok(existing) if !existing.trim().is_empty() => {
+                let mut all = match serde_json::from_str(&existing) {
+                    Ok(Value::Array(all)) => all,
+                    Ok(previous) => vec![previous],
+                    Err(e) => {
+                        panic!("Failed to parse metrics output file \"{path}\" as JSON: {e}");
+                    }
+                };
+                all.push(document.clone());
+                (render(format, &Value::Array(all), rows, true), false)
+            }
+            _ => (render(format, document, rows, true), false),
+        },
+        Format::Jsonl => (render(format, document, rows, true), true),
+        _ => {
+            let empty = !append || metadata(path).map(|m| m.len() == 0).unwrap_or(true);
+            (render(format, document, rows, empty), append)
+        }
+    };
+
+    let mut outfile = match OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+    {
+        Ok(file) => file,
+        Err(e) => {
+            panic!("Failed to open metrics output file \"{path}\": {e}");
+        }
+    };
+
+    if let Err(e) = write!(outfile, "{contents}") {
+        panic!("Failed to write to metrics output file \"{path}\": {e}");
+    }
+}
@@ -0,0 +1,560 @@
+//! Parsing, validation and changes of the (still untyped) guest input,
+//! before it's parsed into `Input`, `PublicInput` and `PrivateInput`.
+
+use serde::{de::DeserializeOwned, Deserialize};
+use std::collections::BTreeMap;
+use toml::{de::ValueDeserializer, Spanned, Table, Value};
+
+/// An argument of the guest's entrypoint function, created by
+/// `input_macros::input_fields!`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputField {
+    pub name: &'static str,
+    pub type_name: &'static str,
+    /// Whether the argument belongs in the public input
+    pub public: bool,
+}
+
+/// A named input of the guest, from its `inputs/<name>/` directory, created
+/// by `input_macros::input_profiles!`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: &'static str,
+    pub public_input: &'static str,
+    pub private_input: &'static str,
+    pub expected_output: Option<&'static str>,
+}
+
+/// Contents of an input file, alongside where they were read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputFile {
+    pub path: String,
+    pub contents: String,
+    pub public: bool,
+}
+
+impl InputFile {
+    fn kind(&self) -> &'static str {
+        kind(self.public)
+    }
+
+    /// Line of the given byte offset, starting from 1
+    fn line(&self, offset: usize) -> usize {
+        self.contents[..offset].matches('\n').count() + 1
+    }
+}
+
+/// Name of the input, which holds public or private arguments
+pub fn kind(public: bool) -> &'static str {
+    if public {
+        "public"
+    } else {
+        "private"
+    }
+}
+
+/// Parses an `--input` override, given as KEY=VALUE, where the value is in
+/// TOML syntax
+pub fn parse_override(argument: &str) -> Result<(String, Value), String> {
+    let (key, value) = argument
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, found \"{argument}\""))?;
+    let value = Value::deserialize(ValueDeserializer::new(value)).map_err(|e| {
+        format!(
+            "\"{value}\" isn't a TOML value: {}",
+            e.to_string().trim_end()
+        )
+    })?;
+    Ok((key.to_string(), value))
+}
+
+/// Splits `--input` overrides into public and private ones, by the
+/// arguments of the guest's entrypoint function. Later overrides of the
+/// same argument win.
+fn split_overrides(
+    fields: &[InputField],
+    overrides: &[(String, Value)],
+    problems: &mut Vec<String>,
+) -> [Table; 2] {
+    let mut split = [Table::new(), Table::new()];
+    for (key, value) in overrides {
+        match fields.iter().find(|field| field.name == key) {
+            Some(field) => {
+                split[if field.public { 0 } else { 1 }].insert(key.clone(), value.clone());
+            }
+            None => problems.push(format!("--input {key}: unknown argument \"{key}\"")),
+        }
+    }
+    split
+}
+
+/// Sets the `--input` overrides in the public or private input, by the
+/// arguments of the guest's entrypoint function.
+pub fn apply_overrides(
+    fields: &[InputField],
+    inputs: [&mut Table; 2],
+    overrides: &[(String, Value)],
+) -> Result<(), String> {
+    let mut problems = Vec::new();
+    let split = split_overrides(fields, overrides, &mut problems);
+    if !problems.is_empty() {
+        return Err(problems.join("\n"));
+    }
+    for (input, overridden) in inputs.into_iter().zip(split) {
+        input.extend(overridden);
+    }
+    Ok(())
+}
+
+/// Parses the public and private input files and checks them against the
+/// arguments of the guest's entrypoint function. The `--input` overrides are
+/// merged over the files' contents.
+///
+/// Unlike deserializing `Input` directly, every problem is reported (in a
+/// single message): syntax errors, unknown, misplaced and missing arguments
+/// and values of the wrong type, alongside the file and line where they
+/// occur.
+pub fn parse<Public: DeserializeOwned, Private: DeserializeOwned>(
+    fields: &[InputField],
+    public: &InputFile,
+    private: &InputFile,
+    overrides: &[(String, Value)],
+) -> Result<(Table, Table), String> {
+    let files = [public, private];
+    let mut problems = Vec::new();
+    let overridden = split_overrides(fields, overrides, &mut problems);
+
+    // Keys are spanned, so their lines can be reported
+    let mut tables = Vec::new();
+    for file in files {
+        match toml::from_str::<BTreeMap<Spanned<String>, Value>>(&file.contents) {
+            Ok(table) => tables.push(Some(table)),
+            Err(e) => {
+                problems.push(format!("{}: {}", file.path, e.to_string().trim_end()));
+                tables.push(None);
+            }
+        }
+    }
+
+    for (file, table) in files.iter().zip(&tables) {
+        for key in table.iter().flat_map(|table| table.keys()) {
+            let line = file.line(key.span().start);
+            match fields.iter().find(|field| field.name == key.as_ref()) {
+                None => problems.push(format!(
+                    "{}:{line}: unknown argument \"{}\"",
+                    file.path,
+                    key.as_ref()
+                )),
+                Some(field) if field.public != file.public => problems.push(format!(
+                    "{}:{line}: \"{}\" is a {} argument, so it belongs in the {} input ({})",
+                    file.path,
+                    field.name,
+                    kind(field.public),
+                    kind(field.public),
+                    files[if field.public { 0 } else { 1 }].path,
+                )),
+                Some(_) => {}
+            }
+        }
+    }
+
+    let mut complete = [true, true];
+    for field in fields {
+        let i = if field.public { 0 } else { 1 };
+        let Some(table) = &tables[i] else {
+            continue;
+        };
+        if table.keys().any(|key| key.as_ref() == field.name)
+            || overridden[i].contains_key(field.name)
+        {
+            continue;
+        }
+        complete[i] = false;
+
+        // Misplaced arguments are already reported
+        let other = &tables[1 - i];
+        if !other
+            .iter()
+            .any(|table| table.keys().any(|key| key.as_ref() == field.name))
+        {
+            problems.push(format!(
+                "missing argument \"{}: {}\", expected in the {} input ({})",
+                field.name,
+                field.type_name,
+                files[i].kind(),
+                files[i].path,
+            ));
+        }
+    }
+
+    let tables = tables
+        .into_iter()
+        .zip(overridden)
+        .map(|(table, overridden)| {
+            table.map(|table| {
+                let mut table = table
+                    .into_iter()
+                    .map(|(key, value)| (key.into_inner(), value))
+                    .collect::<Table>();
+                table.extend(overridden);
+                table
+            })
+        })
+        .collect::<Vec<Option<Table>>>();
+
+    // Deserializing fails on missing arguments
+    if let (Some(table), true) = (&tables[0], complete[0]) {
+        problems.extend(check_types::<Public>(public, table, overrides).err());
+    }
+    if let (Some(table), true) = (&tables[1], complete[1]) {
+        problems.extend(check_types::<Private>(private, table, overrides).err());
+    }
+
+    if !problems.is_empty() {
+        let arguments = |file: &InputFile| {
+            fields
+                .iter()
+                .filter(|field| field.public == file.public)
+                .map(|field| format!("{}: {}", field.name, field.type_name))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let expected = files.map(|file| {
+            format!(
+                "The {} input ({}) takes: {}",
+                file.kind(),
+                file.path,
+                arguments(file)
+            )
+        });
+        return Err(format!(
+            "{}\n\n{}",
+            problems.join("\n"),
+            expected.join("\n")
+        ));
+    }
+
+    let mut tables = tables.into_iter().map(Option::unwrap);
+    Ok((tables.next().unwrap(), tables.next().unwrap()))
+}
+
+/// Reports values of the wrong type in the (merged) input of the given
+/// file.
+///
+/// Their positions are only known when the file's contents are deserialized
+/// directly, so this is only done when no override changes the file.
+fn check_types<T: DeserializeOwned>(
+    file: &InputFile,
+    table: &Table,
+    overrides: &[(String, Value)],
+) -> Result<(), String> {
+    let overridden = overrides
+        .iter()
+        .filter(|(key, _)| table.contains_key(key))
+        .map(|(key, _)| format!("--input {key}"))
+        .collect::<Vec<String>>();
+    if overridden.is_empty() {
+        toml::from_str::<T>(&file.contents)
+            .map(|_| ())
+            .map_err(|e| format!("{}: {}", file.path, e.to_string().trim_end()))
+    } else {
+        table.clone().try_into::<T>().map(|_| ()).map_err(|e| {
+            format!(
+                "{} with {}: {}",
+                file.path,
+                overridden.join(", "),
+                e.to_string().trim_end()
+            )
+        })
+    }
+}
+
+/// Sets the field of the given name, in whichever input defines it, to
+/// `value`.
+///
+/// Numbers are replaced, while arrays and strings are resized to `value`
+/// elements, by repeating their existing elements.
+pub fn set_size(inputs: [&mut Table; 2], field: &str, value: i64) -> Result<(), String> {
+    let Some(current) = inputs.into_iter().find_map(|input| input.get_mut(field)) else {
+        return Err(format!("there is no input field \"{field}\""));
+    };
+
+    let length = || {
+        usize::try_from(value)
+            .map_err(|_| format!("the length of \"{field}\" can't be negative, found {value}"))
+    };
+
+    *current = match current {
+        Value::Integer(_) => Value::Integer(value),
+        Value::Float(_) => Value::Float(value as f64),
+        Value::Array(elements) if elements.is_empty() => {
+            return Err(format!("\"{field}\" is empty, so it can't be resized"));
+        }
+        Value::Array(elements) => {
+            Value::Array(elements.iter().cloned().cycle().take(length()?).collect())
+        }
+        Value::String(string) if string.is_empty() => {
+            return Err(format!("\"{field}\" is empty, so it can't be resized"));
+        }
+        Value::String(string) => Value::String(string.chars().cycle().take(length()?).collect()),
+        other => {
+            return Err(format!(
+                "\"{field}\" is a {}, which can't be swept",
+                other.type_str()
+            ));
+        }
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Public {
+        n: u8,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Private {
+        xs: Vec<u8>,
+        flag: bool,
+    }
+
+    const FIELDS: &[InputField] = &[
+        InputField {
+            name: "n",
+            type_name: "u8",
+            public: true,
+        },
+        InputField {
+            name: "xs",
+            type_name: "Vec<u8>",
+            public: false,
+        },
+        InputField {
+            name: "flag",
+            type_name: "bool",
+            public: false,
+        },
+    ];
+
+    const EXPECTED: &str = "The public input (public.toml) takes: n: u8\n\
+                            The private input (private.toml) takes: xs: Vec<u8>, flag: bool";
+
+    fn file(contents: &str, public: bool) -> InputFile {
+        InputFile {
+            path: format!("{}.toml", kind(public)),
+            contents: contents.to_string(),
+            public,
+        }
+    }
+
+    fn parse_files(
+        public: &str,
+        private: &str,
+        overrides: &[(String, Value)],
+    ) -> Result<(Table, Table), String> {
+        parse::<Public, Private>(
+            FIELDS,
+            &file(public, true),
+            &file(private, false),
+            overrides,
+        )
+    }
+
+    /// The reported problems, without the expected arguments
+    fn problems(public: &str, private: &str) -> String {
+        let message = parse_files(public, private, &[]).unwrap_err();
+        let (problems, expected) = message.split_once("\n\n").unwrap();
+        assert_eq!(expected, EXPECTED);
+        problems.to_string()
+    }
+
+    fn table(contents: &str) -> Table {
+        toml::from_str(contents).unwrap()
+    }
+
+    #[test]
+    fn valid_input() {
+        assert_eq!(
+            parse_files("n = 1", "xs = [1, 2]\nflag = true", &[]),
+            Ok((table("n = 1"), table("xs = [1, 2]\nflag = true")))
+        );
+    }
+
+    #[test]
+    fn unknown_arguments() {
+        assert_eq!(
+            problems("n = 1\n\nm = 2", "xs = []\nflag = true\ny = 3"),
+            "public.toml:3: unknown argument \"m\"\n\
+             private.toml:3: unknown argument \"y\""
+        );
+    }
+
+    #[test]
+    fn misplaced_arguments() {
+        // Misplaced arguments aren't reported as missing as well
+        assert_eq!(
+            problems("n = 1\nflag = true", "xs = []"),
+            "public.toml:2: \"flag\" is a private argument, so it belongs in the private \
+             input (private.toml)"
+        );
+    }
+
+    #[test]
+    fn missing_arguments() {
+        assert_eq!(
+            problems("", "xs = []"),
+            "missing argument \"n: u8\", expected in the public input (public.toml)\n\
+             missing argument \"flag: bool\", expected in the private input (private.toml)"
+        );
+    }
+
+    #[test]
+    fn syntax_and_type_errors() {
+        // Syntax errors are reported first, then values of the wrong type
+        let problems = problems("n = 300", "xs = [\nflag = true");
+        let (syntax, types) = problems.split_once("\npublic.toml").unwrap();
+        assert!(syntax.starts_with("private.toml: TOML parse error at line 2, column 1\n"));
+        assert!(types.starts_with(": TOML parse error at line 1, column 5\n"));
+        assert!(types.ends_with("expected u8"));
+    }
+
+    fn overrides(arguments: &[&str]) -> Vec<(String, Value)> {
+        arguments
+            .iter()
+            .map(|argument| parse_override(argument).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn override_values() {
+        assert_eq!(
+            parse_override("xs=[1, 2]"),
+            Ok(("xs".to_string(), Value::Array(vec![1.into(), 2.into()])))
+        );
+        assert_eq!(
+            parse_override("s=\"a=b\""),
+            Ok(("s".to_string(), Value::String("a=b".to_string())))
+        );
+        assert_eq!(
+            parse_override("n"),
+            Err("expected KEY=VALUE, found \"n\"".to_string())
+        );
+        assert!(parse_override("s=abc")
+            .unwrap_err()
+            .starts_with("\"abc\" isn't a TOML value: "));
+    }
+
+    #[test]
+    fn later_overrides_win() {
+        assert_eq!(
+            parse_files(
+                "n = 1",
+                "xs = [1]\nflag = true",
+                &overrides(&["n=2", "flag=false", "n=3"])
+            ),
+            Ok((table("n = 3"), table("xs = [1]\nflag = false")))
+        );
+
+        let (mut public, mut private) = (table("n = 1"), table("xs = [1]\nflag = true"));
+        apply_overrides(
+            FIELDS,
+            [&mut public, &mut private],
+            &overrides(&["xs=[2]", "n=2", "xs=[3, 4]"]),
+        )
+        .unwrap();
+        assert_eq!(public, table("n = 2"));
+        assert_eq!(private, table("xs = [3, 4]\nflag = true"));
+    }
+
+    #[test]
+    fn overrides_complete_the_input() {
+        assert_eq!(
+            parse_files("", "xs = []", &overrides(&["n=1", "flag=true"])),
+            Ok((table("n = 1"), table("xs = []\nflag = true")))
+        );
+    }
+
+    #[test]
+    fn invalid_overrides() {
+        let message = parse_files("n = 1", "xs = []", &overrides(&["m=1", "flag=1"])).unwrap_err();
+        let (unknown, types) = message.split_once('\n').unwrap();
+        assert_eq!(unknown, "--input m: unknown argument \"m\"");
+        assert!(types.starts_with("private.toml with --input flag: "));
+
+        let (mut public, mut private) = (table("n = 1"), table("xs = []"));
+        assert_eq!(
+            apply_overrides(
+                FIELDS,
+                [&mut public, &mut private],
+                &overrides(&["n=2", "m=1"])
+            ),
+            Err("--input m: unknown argument \"m\"".to_string())
+        );
+        assert_eq!(public, table("n = 1"));
+    }
+
+    #[test]
+    fn set_size_of_numbers() {
+        let (mut public, mut private) = (table("n = 1\nx = 1.5"), table("m = 2"));
+        set_size([&mut public, &mut private], "n", 10).unwrap();
+        set_size([&mut public, &mut private], "x", -3).unwrap();
+        set_size([&mut public, &mut private], "m", 20).unwrap();
+        assert_eq!(public, table("n = 10\nx = -3.0"));
+        assert_eq!(private, table("m = 20"));
+    }
+
+    #[test]
+    fn set_size_repeats_elements() {
+        let (mut public, mut private) = (table("xs = [1, 2]"), table("s = \"abc\""));
+        set_size([&mut public, &mut private], "xs", 5).unwrap();
+        set_size([&mut public, &mut private], "s", 2).unwrap();
+        assert_eq!(public, table("xs = [1, 2, 1, 2, 1]"));
+        assert_eq!(private, table("s = \"ab\""));
+
+        set_size([&mut public, &mut private], "xs", 0).unwrap();
+        assert_eq!(public, table("xs = []"));
+    }
+
+    #[test]
+    fn set_size_errors() {
+        let mut public = table("xs = []\ns = \"\"\nys = [1]\nt = \"a\"\nb = true");
+        let mut private = Table::new();
+        let mut set = |field, value| set_size([&mut public, &mut private], field, value);
+
+        assert_eq!(
+            set("xs", 2),
+            Err("\"xs\" is empty, so it can't be resized".to_string())
+        );
+        assert_eq!(
+            set("s", 2),
+            Err("\"s\" is empty, so it can't be resized".to_string())
+        );
+        assert_eq!(
+            set("ys", -1),
+            Err("the length of \"ys\" can't be negative, found -1".to_string())
+        );
+        assert_eq!(
+            set("t", -1),
+            Err("the length of \"t\" can't be negative, found -1".to_string())
+        );
+        assert_eq!(
+            set("b", 1),
+            Err("\"b\" is a boolean, which can't be swept".to_string())
+        );
+        assert_eq!(
+            set("c", 1),
+            Err("there is no input field \"c\"".to_string())
+        );
+        // Failures leave the input as it was
+        assert_eq!(
+            public,
+            table("xs = []\ns = \"\"\nys = [1]\nt = \"a\"\nb = true")
+        );
+    }
+}
@@ -1,27 +1,79 @@
-use chrono::Local;
-use clap::{Parser, ValueEnum};
+mod capabilities;
+mod children;
+mod config;
+mod envelope;
+mod error;
+mod format;
+mod input;
+pub mod metrics;
+mod phases;
+mod resources;
+mod stats;
+mod sweep;
+
+pub use capabilities::{capabilities, Capabilities};
+pub use children::{run_child, run_child_output};
+use chrono::{DateTime, Local};
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
+pub use config::{Config, Configurable, Knob, KnobType};
 use env_file_reader::read_str;
+pub use envelope::{Envelope, EnvelopeHeader};
+pub use error::HostError;
+pub use format::Format;
+use input::{InputField, InputFile, Profile};
 pub use input_macros::{
     benchmarkable, foreach_input_field, foreach_private_input_field, foreach_public_input_field,
+    input_generator,
+};
+use metrics::{
+    ConfidenceInterval, ErrorInfo, RunMetrics, Sample, Status, SweepMetrics, SweepPoint,
+    SCHEMA_VERSION, TIMEOUT_EXIT_CODE,
 };
-use json::{object, parse, JsonValue, Null};
 use num_traits::NumCast;
+pub use phases::{record_phases, time_phase, Phase, PhaseTimer, Recording};
+use resources::{peak_memory, reset_peak_memory, CpuTimes};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stats::{Summary, CONFIDENCE_LEVEL};
 use std::{
-    collections::*,
     env,
-    fs::{read_to_string, write, OpenOptions},
-    io::{Read, Write},
-    path::Path,
+    fs::{read_to_string, remove_file},
+    process::{exit, Command},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+    thread,
     time::{Duration, Instant},
 };
+use sweep::Sweep;
 
 static DEFAULT_PUBLIC_INPUT: &str =
     include_str!(concat!(env!("INPUTS_DIR"), "/default_public_input.toml"));
 static DEFAULT_PRIVATE_INPUT: &str =
     include_str!(concat!(env!("INPUTS_DIR"), "/default_private_input.toml"));
 static DEFAULT_ENV: &str = include_str!(concat!(env!("INPUTS_DIR"), "/default.env"));
-static PROOF_SIZE_FILE_PATH: &str = "/tmp/proof_size";
+static DEFAULT_EXPECTED_OUTPUT: Option<&str> = input_macros::expected_output!();
+static SIGNATURE: &str = include_str!("../../guests/type.txt");
+static INPUT_FIELDS: &[InputField] = input_macros::input_fields!();
+static PROFILES: &[Profile] = input_macros::input_profiles!();
+
+/// Name of the guest's default input, as a profile
+static DEFAULT_PROFILE: &str = "default";
+
+// These are set by zkvmLib.nix
+static ZKVM: &str = env!("ZKVM");
+static ZKVM_REV: &str = env!("ZKVM_REV");
+static GUEST: &str = env!("GUEST");
+
+/// Size and encoding of the last proof, reported by `output_proof_size`
+static PROOF_SIZE: Mutex<Option<(usize, String)>> = Mutex::new(None);
+
+/// Cycle counts of the last guest run, reported by `output_cycles`
+static CYCLES: Mutex<Option<Cycles>> = Mutex::new(None);
+
+/// When the host started, set by `read_args`
+static STARTED: OnceLock<Instant> = OnceLock::new();
 
 /// A CLI tool for running and benchmarking guest programs inside a zkVM
 /// environment.
@@ -29,10 +81,12 @@ static PROOF_SIZE_FILE_PATH: &str = "/tmp/proof_size";
 /// If you want to run or benchmark your own guest program inside a zkVM,
 /// head on over to https://github.com/blocksense-network/zkVMs-benchmarks
 #[derive(Parser, Debug)]
-#[command(about, long_about = None)]
+// A sweep reruns the host with its own arguments, overriding some of them
+#[command(about, long_about = None, args_override_self = true)]
 struct Cli {
     /// What should the zkVM do with the guest
-    run_type: RunType,
+    #[arg(required_unless_present_any = ["print_schema", "capabilities", "list_config", "list_profiles"])]
+    run_type: Option<RunType>,
 
     /// Path to private input file (in TOML format)
     private_input: Option<String>,
@@ -48,6 +102,16 @@ struct Cli {
     #[arg(short, long, requires = "benchmark")]
     repeat: Option<usize>,
 
+    /// Run the given action this many times before benchmarking, without
+    /// measuring it
+    #[arg(short, long, requires = "benchmark")]
+    warmup: Option<usize>,
+
+    /// Warn when the coefficient of variation of the durations is above the
+    /// given percentage
+    #[arg(long, default_value_t = 5.0, requires = "benchmark")]
+    stability_threshold: f64,
+
     /// Put the benchmark's formatted output into a file of the given path
     #[arg(short = 'o', long, requires = "benchmark")]
     metrics_output: Option<String>,
@@ -55,6 +119,81 @@ struct Cli {
     /// Append the benchmark formatted output to the given file, instead of replacing it
     #[arg(short, long, requires = "benchmark")]
     append: bool,
+
+    /// Format of the benchmark's output
+    #[arg(long, value_enum, default_value_t = Format::Json, requires = "benchmark")]
+    format: Format,
+
+    /// Write the generated proof and its verifying key to a file of the given
+    /// path (only with prove)
+    #[arg(long)]
+    proof_out: Option<String>,
+
+    /// Verify the proof from a file, written with --proof-out, instead of
+    /// generating a new one (only with verify)
+    #[arg(long)]
+    proof_in: Option<String>,
+
+    /// Path to a file with the expected return value of the guest (in TOML
+    /// format). Defaults to the expected output of the input profile, when no
+    /// input files are given
+    #[arg(long)]
+    expected_output: Option<String>,
+
+    /// Stop after the given time (like "90s" or "2h 30m"), exiting with
+    /// status code 124. The benchmark's output records a timeout status
+    #[arg(long, value_parser = humantime::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Print the JSON Schema of the benchmark's output and exit
+    #[arg(long)]
+    print_schema: bool,
+
+    /// Print the operations and features, which this host supports, as JSON
+    /// and exit
+    #[arg(long)]
+    capabilities: bool,
+
+    /// Set a configuration knob, overriding the environment and the guest's
+    /// default.env. Can be given multiple times
+    #[arg(long, value_name = "KEY=VALUE", value_parser = config::parse_key_value)]
+    set: Vec<(String, String)>,
+
+    /// Print the configuration knobs of this host with their values and exit
+    #[arg(long)]
+    list_config: bool,
+
+    /// Run once for every value of an input field, given as
+    /// FIELD=START..END:STEP or FIELD=VALUE,VALUE,... Arrays and strings are
+    /// resized to the value. The benchmark's output is a series of all runs
+    #[arg(long, value_parser = Sweep::parse)]
+    sweep: Option<Sweep>,
+
+    /// Set an input field to a single value of a sweep
+    #[arg(long, hide = true, value_parser = sweep::parse_point)]
+    sweep_point: Option<(String, i64)>,
+
+    /// Generate the input with the guest's input generator, given as
+    /// size=N, instead of reading it from files
+    #[arg(long, value_name = "size=N", value_parser = sweep::parse_generate, conflicts_with_all = ["private_input", "public_input"])]
+    generate: Option<usize>,
+
+    /// Seed of the input generator [default: 0]
+    #[arg(long, requires = "generate")]
+    seed: Option<u64>,
+
+    /// Use one of the guest's named inputs, instead of its default input
+    #[arg(long, conflicts_with_all = ["private_input", "public_input", "generate"])]
+    profile: Option<String>,
+
+    /// Print the names of the guest's input profiles and exit
+    #[arg(long)]
+    list_profiles: bool,
+
+    /// Set an input field to a value (in TOML format), overriding the input
+    /// files, profile or generated input. Can be given multiple times
+    #[arg(long, value_name = "KEY=VALUE", value_parser = input::parse_override)]
+    input: Vec<(String, toml::Value)>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -62,169 +201,888 @@ pub enum RunType {
     Execute,
     Prove,
     Verify,
+    /// Run the guest program directly on the host, without a zkVM. Only
+    /// supported by the native host
+    Native,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RunWith {
     pub run_type: RunType,
     pub benchmarking: bool,
     pub runs: usize,
+    pub warmup: usize,
+    /// Maximum coefficient of variation of stable results
+    pub stability_threshold: f64,
     pub output_file: Option<String>,
     pub append: bool,
+    pub format: Format,
+
+    pub proof_out: Option<String>,
+    pub proof_in: Option<String>,
+
+    pub timeout: Option<Duration>,
 
     pub input: Input,
     pub public_input: PublicInput,
     pub private_input: PrivateInput,
+    /// Name of the input profile, or `None` when the input comes from files,
+    /// the input generator or is changed with `--input`
+    pub profile: Option<String>,
+
+    /// Values of the host's configuration knobs
+    pub config: Config,
+
+    pub expected_return: Option<Return>,
+}
 
-    pub default_env: HashMap<String, String>,
+/// Contents of an `expected_output.toml` file
+#[derive(Debug, Deserialize)]
+struct ExpectedOutput {
+    #[serde(rename = "return")]
+    ret: Return,
 }
 
 impl RunWith {
-    /// Returns a value of the given name from the environment,
-    /// default environment or the given constant, depending on which
-    /// one exists.
-    ///
-    /// If the variable is from either environments, the `then_apply`
-    /// function is executed to convert the String value.
-    ///
-    /// The default environment is taken from the guest's `default.env`
-    pub fn env_then_or<T>(
-        &self,
-        variable_name: &str,
-        then_apply: fn(String) -> Option<T>,
-        else_const: T,
-    ) -> T {
-        env::var(variable_name).ok().and_then(then_apply).unwrap_or(
-            self.default_env
-                .get(variable_name)
-                .and_then(|x| then_apply(x.clone()))
-                .unwrap_or(else_const),
-        )
+    /// Compares the value, returned by the guest, to the expected one (if
+    /// there is such). On mismatch, prints a diff and fails with
+    /// `HostError::UnexpectedOutput`.
+    pub fn check_return(&self, ret: &Return) -> Result<(), HostError> {
+        let Some(expected) = &self.expected_return else {
+            return Ok(());
+        };
+        if expected == ret {
+            return Ok(());
+        }
+
+        eprintln!("--- expected");
+        eprintln!("+++ returned");
+        for line in diff_lines(&format!("{expected:#?}"), &format!("{ret:#?}")) {
+            eprintln!("{line}");
+        }
+        Err(HostError::UnexpectedOutput(format!(
+            "the guest returned {ret:?}, expected {expected:?}"
+        )))
     }
+}
+
+/// Line by line diff of two strings, in a unified diff-like format
+fn diff_lines(expected: &str, found: &str) -> Vec<String> {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let found = found.lines().collect::<Vec<_>>();
 
-    /// Returns a value of the given name from the environment,
-    /// default environment or the given constant, depending on which
-    /// one exists.
-    ///
-    /// The default environment is taken from the guest's `default.env`
-    pub fn env_or(&self, variable_name: &str, else_const: &str) -> String {
-        self.env_then_or(variable_name, |x| Some(x), else_const.to_string())
+    let mut out = Vec::new();
+    for i in 0..expected.len().max(found.len()) {
+        match (expected.get(i), found.get(i)) {
+            (Some(e), Some(f)) if e == f => out.push(format!(" {e}")),
+            (e, f) => {
+                out.extend(e.map(|e| format!("-{e}")));
+                out.extend(f.map(|f| format!("+{f}")));
+            }
+        }
     }
+    out
 }
 
 input_macros::generate_output_type_input_struct!();
 
-pub fn read_args() -> RunWith {
+/// Input generator of the guest, called with the size of the input and a
+/// seed. Created by `input_generator!` from the guest's
+/// `#[guests_macro::input_generator]` function
+pub type Generator = fn(usize, u64) -> Input;
+
+/// Exits with an invalid input error. The benchmark isn't set up yet, so the
+/// error is only reported through the exit code.
+fn invalid_input(message: String) -> ! {
+    let error = HostError::InvalidInput(message);
+    eprintln!("{error}");
+    exit(error.exit_code());
+}
+
+fn read_input_file(path: &str) -> String {
+    read_to_string(path).unwrap_or_else(|e| invalid_input(format!("couldn't read \"{path}\": {e}")))
+}
+
+/// Runs the guest's input generator and splits the input into its public
+/// and private fields, like those of the guest's default input
+fn generate_input(generator: Generator, size: usize, seed: u64) -> (toml::Table, toml::Table) {
+    let input = toml::Table::try_from(generator(size, seed))
+        .unwrap_or_else(|e| invalid_input(format!("failed to convert the generated input: {e}")));
+    input.into_iter().partition(|(field, _)| {
+        INPUT_FIELDS
+            .iter()
+            .any(|f| f.public && f.name == field.as_str())
+    })
+}
+
+/// Parses the command line arguments and input files.
+///
+/// `B` declares what the host supports, so unsupported operations are
+/// rejected before anything is run, and its configuration knobs.
+/// `generator` is the guest's input generator, if it has one, used with
+/// `--generate`.
+pub fn read_args<B: Capabilities + Configurable>(generator: Option<Generator>) -> RunWith {
+    STARTED.get_or_init(Instant::now);
     let cli = Cli::parse();
 
-    let public_contents: String = if cli.public_input.is_some() {
-        read_to_string(cli.public_input.unwrap()).unwrap()
-    } else {
-        DEFAULT_PUBLIC_INPUT.to_string()
+    if cli.print_schema {
+        if cli.sweep.is_some() {
+            println!("{}", metrics::schema::<SweepMetrics<RunMetrics>>());
+        } else {
+            println!("{}", metrics::schema::<RunMetrics>());
+        }
+        std::process::exit(0);
+    }
+    if cli.capabilities {
+        let capabilities = capabilities::<B>();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&capabilities).expect("failed to serialize capabilities")
+        );
+        std::process::exit(0);
+    }
+
+    let default_env = read_str(DEFAULT_ENV).unwrap();
+    let config = Config::resolve(B::KNOBS, &cli.set, &default_env).unwrap_or_else(|message| {
+        Cli::command()
+            .error(ErrorKind::InvalidValue, message)
+            .exit()
+    });
+    if cli.list_config {
+        print_config(&config);
+        std::process::exit(0);
+    }
+    if cli.list_profiles {
+        println!("{DEFAULT_PROFILE}");
+        for profile in PROFILES {
+            println!("{}", profile.name);
+        }
+        std::process::exit(0);
+    }
+
+    let default_profile = Profile {
+        name: DEFAULT_PROFILE,
+        public_input: DEFAULT_PUBLIC_INPUT,
+        private_input: DEFAULT_PRIVATE_INPUT,
+        expected_output: DEFAULT_EXPECTED_OUTPUT,
     };
-    let private_contents: String = if cli.private_input.is_some() {
-        read_to_string(cli.private_input.unwrap()).unwrap()
-    } else {
-        DEFAULT_PRIVATE_INPUT.to_string()
+    let profile = match &cli.profile {
+        None => &default_profile,
+        Some(name) if name == DEFAULT_PROFILE => &default_profile,
+        Some(name) => PROFILES
+            .iter()
+            .find(|profile| profile.name == name)
+            .unwrap_or_else(|| {
+                Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("{GUEST} has no input profile \"{name}\", see --list-profiles"),
+                    )
+                    .exit()
+            }),
     };
-    let input: Input = toml::from_str(&(public_contents.clone() + &private_contents)).unwrap();
-    let public_input: PublicInput = toml::from_str(&public_contents).unwrap();
-    let private_input: PrivateInput = toml::from_str(&private_contents).unwrap();
 
-    let default_env = read_str(DEFAULT_ENV).unwrap();
+    let run_type = cli.run_type.unwrap();
 
-    RunWith {
-        run_type: cli.run_type,
+    if cli.proof_out.is_some() && run_type != RunType::Prove {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--proof-out can only be used with prove",
+            )
+            .exit();
+    }
+    if cli.proof_in.is_some() && run_type != RunType::Verify {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--proof-in can only be used with verify",
+            )
+            .exit();
+    }
+    // Only the native host links the guest program directly
+    if (run_type == RunType::Native) != (ZKVM == "native") {
+        let message = if ZKVM == "native" {
+            "the native host can only be used with native"
+        } else {
+            "native can only be used with the native host"
+        };
+        Cli::command()
+            .error(ErrorKind::InvalidValue, message)
+            .exit();
+    }
+
+    // A sweep checks its first value here, so an invalid field fails before
+    // the host is run for every value
+    let sweep_point = cli.sweep_point.clone().or_else(|| {
+        cli.sweep
+            .as_ref()
+            .map(|sweep| (sweep.field.clone(), sweep.values[0]))
+    });
+
+    let expected_contents = match (&cli.expected_output, &cli.public_input, &cli.private_input) {
+        // The host can't check it, an explicit one is rejected as unsupported
+        _ if !B::OUTPUT_CHECKING.contains(&run_type) => None,
+        (Some(path), _, _) => Some(read_input_file(path)),
+        (None, None, None)
+            if sweep_point.is_none() && cli.generate.is_none() && cli.input.is_empty() =>
+        {
+            profile.expected_output.map(|x| x.to_string())
+        }
+        // The profile's expected output only holds for its own input
+        _ => None,
+    };
+    let expected_return = expected_contents.map(|contents| {
+        toml::from_str::<ExpectedOutput>(&contents)
+            .unwrap_or_else(|e| invalid_input(format!("failed to parse expected output: {e}")))
+            .ret
+    });
+
+    fn parse_error<T>(e: toml::de::Error) -> T {
+        invalid_input(format!("failed to parse input: {e}"))
+    }
+    let input_file = |path: &Option<String>, default: &str, public: bool| match path {
+        Some(path) => InputFile {
+            path: path.clone(),
+            contents: read_input_file(path),
+            public,
+        },
+        None if profile.name == DEFAULT_PROFILE => InputFile {
+            path: format!("guests/{GUEST}/default_{}_input.toml", input::kind(public)),
+            contents: default.to_string(),
+            public,
+        },
+        None => InputFile {
+            path: format!(
+                "guests/{GUEST}/inputs/{}/{}_input.toml",
+                profile.name,
+                input::kind(public)
+            ),
+            contents: default.to_string(),
+            public,
+        },
+    };
+    let (mut public_table, mut private_table) = match cli.generate {
+        Some(size) => {
+            let Some(generator) = generator else {
+                Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("{GUEST} has no input generator"),
+                    )
+                    .exit();
+            };
+
+            // Sweeping the size regenerates the input for every value
+            let size = match &sweep_point {
+                Some((field, value)) if field == "size" => {
+                    usize::try_from(*value).unwrap_or_else(|_| {
+                        invalid_input(format!("the size can't be negative, found {value}"))
+                    })
+                }
+                _ => size,
+            };
+            let (mut public_table, mut private_table) =
+                generate_input(generator, size, cli.seed.unwrap_or(0));
+            input::apply_overrides(
+                INPUT_FIELDS,
+                [&mut public_table, &mut private_table],
+                &cli.input,
+            )
+            .unwrap_or_else(|message| invalid_input(message));
+            (public_table, private_table)
+        }
+        None => input::parse::<PublicInput, PrivateInput>(
+            INPUT_FIELDS,
+            &input_file(&cli.public_input, profile.public_input, true),
+            &input_file(&cli.private_input, profile.private_input, false),
+            &cli.input,
+        )
+        .unwrap_or_else(|message| invalid_input(message)),
+    };
+    match &sweep_point {
+        Some((field, _)) if cli.generate.is_some() && field == "size" => {}
+        Some((field, value)) => {
+            input::set_size([&mut public_table, &mut private_table], field, *value)
+                .unwrap_or_else(|message| invalid_input(message));
+        }
+        None => {}
+    }
+
+    let mut input_table = public_table.clone();
+    input_table.extend(private_table.clone());
+    let input: Input = input_table.try_into().unwrap_or_else(parse_error);
+    let public_input: PublicInput = public_table.try_into().unwrap_or_else(parse_error);
+    let private_input: PrivateInput = private_table.try_into().unwrap_or_else(parse_error);
+
+    let run_info = RunWith {
+        run_type,
         benchmarking: cli.benchmark,
         runs: cli.repeat.unwrap_or(1),
+        warmup: cli.warmup.unwrap_or(0),
+        stability_threshold: cli.stability_threshold / 100.0,
         output_file: cli.metrics_output,
         append: cli.append,
+        format: cli.format,
+
+        proof_out: cli.proof_out,
+        proof_in: cli.proof_in,
+
+        timeout: cli.timeout,
 
         input,
         public_input,
         private_input,
+        profile: (cli.public_input.is_none()
+            && cli.private_input.is_none()
+            && cli.generate.is_none()
+            && cli.input.is_empty()
+            && sweep_point.is_none())
+        .then(|| profile.name.to_string()),
+
+        config,
 
-        default_env,
+        expected_return,
+    };
+
+    let unsupported = if !B::RUN_TYPES.contains(&run_type) {
+        let operation = run_type.to_possible_value().unwrap();
+        Some(format!("{ZKVM} doesn't support {}", operation.get_name()))
+    } else if cli.expected_output.is_some() && !B::OUTPUT_CHECKING.contains(&run_type) {
+        let operation = run_type.to_possible_value().unwrap();
+        Some(format!(
+            "{ZKVM} can't check the output of {}",
+            operation.get_name()
+        ))
+    } else if run_info.proof_out.is_some() && !B::PROOF_EXPORT {
+        Some(format!("{ZKVM} can't write proofs to a file"))
+    } else if run_info.proof_in.is_some() && !B::PROOF_IMPORT {
+        Some(format!("{ZKVM} can't verify a proof from a file"))
+    } else {
+        None
+    };
+    if let Some(message) = unsupported {
+        run_info.fail(HostError::Unsupported(message));
     }
+
+    if let (Some(sweep), None) = (&cli.sweep, &cli.sweep_point) {
+        run_sweep(&run_info, sweep);
+    }
+
+    if let Some(timeout) = run_info.timeout {
+        start_watchdog(run_info.clone(), timeout);
+    }
+    run_info
 }
 
-pub fn output_proof_size<T>(proof: &T) {
-    output_proof_size_raw(std::mem::size_of_val(proof));
+fn print_config(config: &Config) {
+    if config.iter().next().is_none() {
+        println!("{ZKVM} has no configuration knobs");
+    }
+    for (knob, setting) in config.iter() {
+        println!(
+            "{} = {} (from {})",
+            knob.name, setting.value, setting.source
+        );
+        println!("    {} (default: {})", knob.kind, knob.default);
+        println!("    {}", knob.description);
+    }
 }
 
-pub fn output_proof_size_raw(size: usize) {
-    std::fs::write(PROOF_SIZE_FILE_PATH, size.to_string()).expect(&format!(
-        "Couldn't write proof size to \"{PROOF_SIZE_FILE_PATH}\"!"
-    ));
+/// Canonical serialization of a zkVM's proof and verifying key.
+///
+/// Each host implements this for its own backend (marker) type, so the
+/// reported proof size is the amount of bytes a verifier would receive,
+/// rather than the in-memory size of the proof structure. The same encoding
+/// is used when persisting proofs (in envelopes) with `--proof-out` and
+/// `--proof-in`.
+pub trait ProofSerializer {
+    /// The proof type, as returned by the zkVM
+    type Proof;
+
+    /// Everything, besides the proof, which the verifier needs (verifying
+    /// key, image ID, ...). Unit if the host can reconstruct it on its own.
+    type VerifyingKey;
+
+    /// Name of the serialization format, reported alongside the proof size
+    const ENCODING: &'static str;
+
+    /// Serializes the proof into its canonical byte representation
+    fn serialize_proof(proof: &Self::Proof) -> Vec<u8>;
+
+    /// Inverse of `serialize_proof`. Fails on bytes, which aren't a proof
+    /// (for example a truncated or corrupted file)
+    fn deserialize_proof(bytes: &[u8]) -> Result<Self::Proof, HostError>;
+
+    /// Serializes the verifying key into its canonical byte representation
+    fn serialize_key(key: &Self::VerifyingKey) -> Vec<u8>;
+
+    /// Inverse of `serialize_key`. Fails on bytes, which aren't a verifying
+    /// key
+    fn deserialize_key(bytes: &[u8]) -> Result<Self::VerifyingKey, HostError>;
+
+    /// Human readable digest of the verifying key, stored in proof envelopes
+    fn key_digest(_key: &Self::VerifyingKey) -> Option<String> {
+        None
+    }
 }
 
-fn mean(xs: &Vec<f32>) -> f32 {
-    if xs.len() == 1 {
-        xs[0]
-    } else {
-        statistical::mean(&xs)
+/// Writes the proof and verifying key, alongside information about the zkVM,
+/// guest program and its public input and output, to an envelope file.
+///
+/// `output` is the decoded public output, if the zkVM exposes it.
+pub fn write_proof<B: ProofSerializer>(
+    path: &str,
+    run_info: &RunWith,
+    output: Option<&Output>,
+    proof: &B::Proof,
+    key: &B::VerifyingKey,
+) -> Result<(), HostError> {
+    let header = EnvelopeHeader {
+        zkvm_name: ZKVM.to_string(),
+        zkvm_rev: ZKVM_REV.to_string(),
+        program_name: GUEST.to_string(),
+        signature: SIGNATURE.to_string(),
+        public_input: serde_json::to_value(&run_info.public_input)
+            .expect("failed to serialize public input"),
+        output: serde_json::to_value(output).expect("failed to serialize output"),
+        key_digest: B::key_digest(key),
+        proof_encoding: B::ENCODING.to_string(),
+    };
+
+    Envelope {
+        header,
+        key: B::serialize_key(key),
+        proof: B::serialize_proof(proof),
     }
+    .write(path)
+    .map_err(HostError::InvalidInput)
 }
 
-fn stddev(xs: &Vec<f32>) -> f32 {
-    if xs.len() == 1 {
-        0.0
-    } else {
-        statistical::standard_deviation(&xs, None)
+/// Reads a proof and its verifying key from an envelope file, written by
+/// `write_proof`.
+///
+/// The envelope must have been created by the same zkVM for the same guest
+/// program, otherwise it's invalid input.
+pub fn read_proof<B: ProofSerializer>(
+    path: &str,
+) -> Result<(B::Proof, B::VerifyingKey), HostError> {
+    let envelope = Envelope::read(path).map_err(HostError::InvalidInput)?;
+    let header = &envelope.header;
+
+    if header.zkvm_name != ZKVM || header.program_name != GUEST {
+        return Err(HostError::InvalidInput(format!(
+            "proof file \"{path}\" was generated by {} for {}, expected {ZKVM} for {GUEST}",
+            header.zkvm_name, header.program_name
+        )));
+    }
+    if header.signature != SIGNATURE {
+        return Err(HostError::InvalidInput(format!(
+            "proof file \"{path}\" was generated for \"{}\", expected \"{SIGNATURE}\"",
+            header.signature
+        )));
     }
+    if header.proof_encoding != B::ENCODING {
+        return Err(HostError::InvalidInput(format!(
+            "proof file \"{path}\" uses {} encoding, expected {}",
+            header.proof_encoding,
+            B::ENCODING
+        )));
+    }
+
+    // Whatever the backend reports, the proof file is what's malformed
+    let malformed = |e: HostError| {
+        HostError::InvalidInput(format!(
+            "couldn't deserialize proof file \"{path}\": {}",
+            e.message()
+        ))
+    };
+    Ok((
+        B::deserialize_proof(&envelope.proof).map_err(malformed)?,
+        B::deserialize_key(&envelope.key).map_err(malformed)?,
+    ))
+}
+
+/// Checks that the verifying key of a proof file, as returned by
+/// `read_proof`, is the one the host derived itself from the guest program.
+///
+/// The key inside the file isn't trusted: proofs are always verified with
+/// the expected key, this only reports a mismatch as invalid input.
+pub fn check_proof_key<B: ProofSerializer>(
+    path: &str,
+    key: &B::VerifyingKey,
+    expected: &B::VerifyingKey,
+) -> Result<(), HostError> {
+    if B::serialize_key(key) == B::serialize_key(expected) {
+        return Ok(());
+    }
+    let message = match (B::key_digest(key), B::key_digest(expected)) {
+        (Some(key), Some(expected)) => {
+            format!("proof file \"{path}\" has verifying key {key}, expected {expected} of {GUEST}")
+        }
+        _ => format!("proof file \"{path}\" has a different verifying key than {GUEST}"),
+    };
+    Err(HostError::InvalidInput(message))
+}
+
+/// Serializes the proof with the backend's canonical encoding and records
+/// the resulting byte length for the metrics output.
+pub fn output_proof_size<B: ProofSerializer>(proof: &B::Proof) {
+    output_proof_size_raw(B::serialize_proof(proof).len(), B::ENCODING);
 }
 
-/// Used by the "benchmarkable" macro. Takes run_info and two vectors of start and
-/// end instants for each benchmark iteration.
-pub fn emit_benchmark_results(run_info: RunWith, starts: Vec<Instant>, ends: Vec<Instant>) {
-    let now = Local::now();
-    let mut run = JsonValue::new_object();
-    let duration = *ends.last().unwrap() - *starts.first().unwrap();
+/// Records an already known proof size, in Bytes, and the encoding in which
+/// it was measured.
+pub fn output_proof_size_raw(size: usize, encoding: &str) {
+    *PROOF_SIZE.lock().unwrap() = Some((size, encoding.to_string()));
+}
 
-    run["timeStarted"] = (now - duration).to_string().into();
-    run["runs"] = run_info.runs.into();
-    run["totalDuration"] = duration.as_secs_f32().into();
+/// Amount of work, done by the guest program, as counted by the zkVM.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Cycles {
+    /// Cycles, instructions or trace steps of the guest program, whichever
+    /// the zkVM counts
+    pub cycles: u64,
+    /// Additional cycles, spent on paging memory in and out
+    pub paging_cycles: Option<u64>,
+    /// Amount of segments (shards) the execution was split into
+    pub segments: Option<u64>,
+}
+
+/// Records the cycle counts of the guest program for the metrics output.
+pub fn output_cycles(cycles: Cycles) {
+    *CYCLES.lock().unwrap() = Some(cycles);
+}
+
+/// Start of a benchmark iteration, as recorded by the "benchmarkable" macro.
+///
+/// The wall-clock time is only used for reporting, durations are always
+/// measured with the monotonic `Instant`.
+///
+/// Also resets the peak memory, so it's measured for every iteration
+/// separately.
+#[derive(Debug, Copy, Clone)]
+pub struct IterationStart {
+    pub wall: DateTime<Local>,
+    pub instant: Instant,
+    pub cpu: CpuTimes,
+    /// Whether the peak memory was reset, otherwise it isn't reported
+    pub peak_reset: bool,
+}
+
+impl IterationStart {
+    pub fn now() -> IterationStart {
+        // Resetting comes first, so it isn't part of the duration
+        let peak_reset = reset_peak_memory();
+        let wall = Local::now();
+        // Same order as in IterationEnd, so CPU time and duration cover the
+        // same span
+        let instant = Instant::now();
+        let cpu = CpuTimes::now();
+        IterationStart {
+            wall,
+            instant,
+            cpu,
+            peak_reset,
+        }
+    }
+}
+
+/// End of a benchmark iteration, as recorded by the "benchmarkable" macro.
+#[derive(Debug, Copy, Clone)]
+pub struct IterationEnd {
+    pub instant: Instant,
+    pub cpu: CpuTimes,
+    /// Peak resident set size in Bytes since the iteration's start
+    pub peak_memory: Option<u64>,
+}
+
+impl IterationEnd {
+    pub fn now(start: &IterationStart) -> IterationEnd {
+        let instant = Instant::now();
+        let cpu = CpuTimes::now();
+        IterationEnd {
+            instant,
+            cpu,
+            // Without a reset, it would be the peak over the host's lifetime
+            peak_memory: peak_memory().filter(|_| start.peak_reset),
+        }
+    }
+}
+
+/// Starts and ends of the measured iterations of the current benchmark
+static ITERATIONS: Mutex<(Vec<IterationStart>, Vec<IterationEnd>)> =
+    Mutex::new((Vec::new(), Vec::new()));
+
+/// Set once the host has finished, either normally, by an error or by a
+/// timeout
+static FINISHED: AtomicBool = AtomicBool::new(false);
+
+/// Set once the timeout watchdog has been started
+static WATCHDOG_ARMED: AtomicBool = AtomicBool::new(false);
+
+/// Used by the "benchmarkable" macro, at the start of a measured iteration
+pub fn start_iteration() {
+    ITERATIONS.lock().unwrap().0.push(IterationStart::now());
+}
+
+/// Used by the "benchmarkable" macro, at the end of a measured iteration
+pub fn end_iteration() {
+    let mut iterations = ITERATIONS.lock().unwrap();
+    let start = *iterations
+        .0
+        .last()
+        .expect("iteration ended before it started");
+    iterations.1.push(IterationEnd::now(&start));
+}
+
+/// Stops the host once the timeout has passed. The finished iterations are
+/// emitted with a timeout status (if benchmarking) and child processes,
+/// started with `run_child`, are killed, before exiting with
+/// `TIMEOUT_EXIT_CODE`.
+fn start_watchdog(run_info: RunWith, timeout: Duration) {
+    WATCHDOG_ARMED.store(true, Ordering::SeqCst);
+    thread::spawn(move || {
+        thread::sleep(timeout);
+        if FINISHED.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        eprintln!("Timed out after {}!", humantime::format_duration(timeout));
+        children::kill_children();
+        if run_info.benchmarking {
+            write_metrics(&run_info, Status::Timeout, None);
+        }
+        exit(TIMEOUT_EXIT_CODE);
+    });
+}
+
+/// Marks the host as finished. If the watchdog has already timed out, it is
+/// writing the metrics and will exit the process, so this never returns.
+fn finish() {
+    if FINISHED.swap(true, Ordering::SeqCst) && WATCHDOG_ARMED.load(Ordering::SeqCst) {
+        loop {
+            thread::park();
+        }
+    }
+}
+
+impl RunWith {
+    /// Reports all finished iterations (if benchmarking), after the run's last
+    /// fallible step has succeeded.
+    pub fn succeed(&self) {
+        finish();
+        if self.benchmarking {
+            write_metrics(self, Status::Success, None);
+        }
+    }
+
+    /// Reports the error, alongside all finished iterations (if
+    /// benchmarking), and exits with the error's exit code.
+    pub fn fail(&self, error: HostError) -> ! {
+        eprintln!("{error}");
+
+        finish();
+        if self.benchmarking {
+            write_metrics(self, error.kind().status(), Some(error.info()));
+        }
+        exit(error.exit_code());
+    }
+}
+
+/// Writes the metrics of all finished iterations.
+///
+/// Unless successful, the total duration is the time since the host started,
+/// instead of the time between the first and last iteration.
+fn write_metrics(run_info: &RunWith, status: Status, error: Option<ErrorInfo>) {
+    let (starts, ends) = std::mem::take(&mut *ITERATIONS.lock().unwrap());
+    // On timeout or error, the last started iteration hasn't finished
+    let starts = &starts[..ends.len()];
+    let runs = ends.len();
+
+    let duration = match (status, starts.first(), ends.last()) {
+        (Status::Success, Some(first), Some(last)) => last.instant - first.instant,
+        _ => STARTED.get().unwrap().elapsed(),
+    };
 
     let durations = starts
         .iter()
         .zip(ends.iter())
-        .map(|(&s, &e)| (e - s).as_secs_f32())
-        .collect::<Vec<f32>>();
-
-    run["mean"] = mean(&durations).into();
-    run["deviation"] = stddev(&durations).into();
-    run["min"] = (*durations
-        .iter()
-        .min_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap())
-    .into();
-    run["max"] = (*durations
+        .map(|(s, e)| (e.instant - s.instant).as_secs_f64())
+        .collect::<Vec<f64>>();
+    let cpu_times = starts
         .iter()
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap())
-    .into();
-
-    run["memory"] = Null;
-    run["proofSize"] = Null;
-
-    if let Some(path) = run_info.output_file {
-        let mut outfile = match OpenOptions::new()
-            .write(true)
-            .create(true)
-            .append(run_info.append)
-            .truncate(!run_info.append)
-            .open(&path)
-        {
-            Ok(file) => file,
-            Err(e) => {
-                panic!("Failed to open metrics output file \"{path}\": {e}");
-            }
-        };
+        .zip(ends.iter())
+        .map(|(s, e)| e.cpu.since(&s.cpu))
+        .collect::<Vec<CpuTimes>>();
 
-        if let Err(e) = writeln!(outfile, "{}", run.dump()) {
-            panic!("Failed to write to metrics output file \"{path}\": {e}");
+    let summary = (!durations.is_empty()).then(|| Summary::new(&durations));
+    if let Some(summary) = &summary {
+        if summary.cv > run_info.stability_threshold {
+            eprintln!(
+                "Warning: results are unstable! Coefficient of variation is {:.2}%, above the {:.2}% threshold",
+                summary.cv * 100.0,
+                run_info.stability_threshold * 100.0
+            );
         }
-    } else {
-        println!("{}", run.dump());
     }
+    let mean_of = |xs: Vec<f64>| (runs > 0).then(|| xs.iter().sum::<f64>() / runs as f64);
+
+    let proof_size = PROOF_SIZE.lock().unwrap().clone();
+    let cycles = *CYCLES.lock().unwrap();
+
+    let run = RunMetrics {
+        schema_version: SCHEMA_VERSION,
+        status,
+        error,
+        time_started: starts.first().map(|s| s.wall.to_string()),
+        runs,
+        warmup: run_info.warmup,
+        total_duration: duration.as_secs_f64(),
+        mean: summary.as_ref().map(|s| s.mean),
+        deviation: summary.as_ref().map(|s| s.deviation),
+        min: summary.as_ref().map(|s| s.min),
+        max: summary.as_ref().map(|s| s.max),
+        median: summary.as_ref().map(|s| s.median),
+        p90: summary.as_ref().map(|s| s.p90),
+        p99: summary.as_ref().map(|s| s.p99),
+        cv: summary.as_ref().map(|s| s.cv),
+        confidence_interval: summary.as_ref().map(|s| ConfidenceInterval {
+            level: CONFIDENCE_LEVEL,
+            low: s.ci_low,
+            high: s.ci_high,
+        }),
+        outliers: summary.as_ref().map(|s| s.outliers),
+        stable: summary
+            .as_ref()
+            .map(|s| s.cv <= run_info.stability_threshold),
+        samples: starts
+            .iter()
+            .zip(ends.iter())
+            .zip(durations.iter().zip(cpu_times.iter()))
+            .map(|((s, e), (&d, cpu))| Sample {
+                time_started: s.wall.to_string(),
+                duration: d,
+                peak_memory: e.peak_memory,
+                user_time: cpu.user,
+                system_time: cpu.system,
+            })
+            .collect(),
+        phases: phases::take_phases(runs.max(1)),
+        memory: ends.iter().filter_map(|e| e.peak_memory).max(),
+        user_time: mean_of(cpu_times.iter().map(|c| c.user).collect()),
+        system_time: mean_of(cpu_times.iter().map(|c| c.system).collect()),
+        proof_size: proof_size.as_ref().map(|(size, _)| *size),
+        proof_encoding: proof_size.map(|(_, encoding)| encoding),
+        cycles: cycles.map(|c| c.cycles),
+        paging_cycles: cycles.and_then(|c| c.paging_cycles),
+        segments: cycles.and_then(|c| c.segments),
+        profile: run_info.profile.clone(),
+        config: run_info.config.values(),
+    };
+    let run = serde_json::to_value(&run).expect("failed to serialize metrics");
+
+    format::write_metrics(
+        run_info.format,
+        &run_info.output_file,
+        run_info.append,
+        &run,
+        &[table_row(run_info, &run)],
+    );
+}
+
+/// Row of the tabular formats for the given metrics
+fn table_row(run_info: &RunWith, metrics: &Value) -> Value {
+    // Identifies the row in tabular formats
+    let mut row = metrics.clone();
+    row["zkvmName"] = ZKVM.into();
+    row["zkvmRev"] = ZKVM_REV.into();
+    row["programName"] = GUEST.into();
+    row["operation"] = run_info
+        .run_type
+        .to_possible_value()
+        .unwrap()
+        .get_name()
+        .into();
+    row
+}
+
+/// Runs this host once for every value of the sweep, with the same
+/// arguments, and exits. When benchmarking, the metrics of all runs are
+/// written as a single series.
+///
+/// Exits with the status code of the first failed run, if any.
+fn run_sweep(run_info: &RunWith, sweep: &Sweep) -> ! {
+    let host = env::current_exe().expect("failed to locate the host executable");
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let metrics_path = format!("/tmp/sweep_metrics_{}", std::process::id());
+
+    let mut points = Vec::new();
+    let mut exit_code = 0;
+    for (value, point) in sweep.values.iter().zip(sweep.points()) {
+        println!("== {point} ==");
+
+        let mut command = Command::new(&host);
+        command.args(&args).args(["--sweep-point", &point]);
+        if run_info.benchmarking {
+            let _ = remove_file(&metrics_path);
+            command.args(["--metrics-output", &metrics_path, "--format", "json"]);
+        }
+        let status = command.status().expect("failed to run the host");
+
+        let code = status.code().unwrap_or(-1);
+        if exit_code == 0 && code != 0 {
+            exit_code = code;
+        }
+
+        if run_info.benchmarking {
+            // Hosts which fail before benchmarking don't write metrics
+            let metrics = match read_to_string(&metrics_path) {
+                Ok(raw_data) => serde_json::from_str(&raw_data).unwrap_or_else(|e| {
+                    panic!("Couldn't parse metrics file \"{metrics_path}\": {e}")
+                }),
+                Err(_) => RunMetrics::failed(
+                    ErrorInfo {
+                        kind: metrics::ErrorKind::from_exit_code(code),
+                        message: format!("exited with status code {code}"),
+                    },
+                    0.0,
+                ),
+            };
+            points.push(SweepPoint {
+                value: *value,
+                metrics,
+            });
+        }
+    }
+    let _ = remove_file(&metrics_path);
+
+    if run_info.benchmarking {
+        let series = SweepMetrics {
+            schema_version: SCHEMA_VERSION,
+            field: sweep.field.clone(),
+            points,
+        };
+        let rows = series
+            .points
+            .iter()
+            .map(|point| {
+                let metrics =
+                    serde_json::to_value(&point.metrics).expect("failed to serialize metrics");
+                let mut row = table_row(run_info, &metrics);
+                row["sweepField"] = series.field.clone().into();
+                row["sweepValue"] = point.value.into();
+                row
+            })
+            .collect::<Vec<Value>>();
+
+        format::write_metrics(
+            run_info.format,
+            &run_info.output_file,
+            run_info.append,
+            &serde_json::to_value(&series).expect("failed to serialize metrics"),
+            &rows,
+        );
+    }
+    exit(exit_code);
 }
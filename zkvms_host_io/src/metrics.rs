@@ -0,0 +1,429 @@
+//! Structure of the metrics output.
+//!
+//! This file is also included by zkvms_guest_io, so the zkVM hosts and the
+//! orchestrator agree on the format of the metrics and `--capabilities`
+//! outputs. Their JSON Schema, as printed with `--print-schema`, is derived
+//! from these types.
+
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Version of the metrics output format. Incremented on every released
+/// change of the types below, which could break parsers.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Exit code of a host, which was stopped by its `--timeout`
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// How a benchmarked operation ended
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// All runs finished
+    Success,
+    /// The operation was stopped, because it exceeded the `--timeout`
+    Timeout,
+    /// The operation failed, see `error`
+    Error,
+    /// The zkVM doesn't support the operation, see `error`
+    Unsupported,
+}
+
+/// Why an operation failed. Every kind has its own exit code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorKind {
+    /// The zkVM doesn't support the operation
+    Unsupported,
+    /// The input (or proof) files couldn't be read or are malformed, or the
+    /// proof file couldn't be written
+    InvalidInput,
+    /// The guest program panicked or otherwise failed while running
+    GuestPanic,
+    /// The zkVM failed to generate a proof
+    ProverError,
+    /// The proof was rejected by the verifier
+    VerificationFailed,
+    /// The guest returned a different value than the expected output
+    UnexpectedOutput,
+    /// The host failed for another reason, like an unexpected panic
+    Other,
+}
+
+impl ErrorKind {
+    const ALL: [ErrorKind; 7] = [
+        ErrorKind::Unsupported,
+        ErrorKind::InvalidInput,
+        ErrorKind::GuestPanic,
+        ErrorKind::ProverError,
+        ErrorKind::VerificationFailed,
+        ErrorKind::UnexpectedOutput,
+        ErrorKind::Other,
+    ];
+
+    /// Exit code of a host, which failed with this kind of error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorKind::Unsupported => 3,
+            ErrorKind::InvalidInput => 4,
+            ErrorKind::GuestPanic => 5,
+            ErrorKind::ProverError => 6,
+            ErrorKind::VerificationFailed => 7,
+            ErrorKind::UnexpectedOutput => 8,
+            // Same as the exit code of Rust panics
+            ErrorKind::Other => 101,
+        }
+    }
+
+    /// Status of an operation, which failed with this kind of error
+    pub fn status(&self) -> Status {
+        match self {
+            ErrorKind::Unsupported => Status::Unsupported,
+            _ => Status::Error,
+        }
+    }
+
+    /// Inverse of `exit_code`. Unknown codes are `Other`.
+    pub fn from_exit_code(code: i32) -> ErrorKind {
+        ErrorKind::ALL
+            .into_iter()
+            .find(|kind| kind.exit_code() == code)
+            .unwrap_or(ErrorKind::Other)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ErrorInfo {
+    pub kind: ErrorKind,
+    /// Human readable description of the error
+    pub message: String,
+}
+
+/// Metrics of a single operation, emitted by a `ZKVM/PROGRAM` (or native)
+/// host with `--benchmark`.
+///
+/// Statistics are computed over the finished runs, so they are null when
+/// no run finished before a timeout or an error.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMetrics {
+    pub schema_version: u32,
+    pub status: Status,
+    /// Set only when the status is `error` or `unsupported`
+    pub error: Option<ErrorInfo>,
+    /// Timestamp of the start of the first measured run
+    pub time_started: Option<String>,
+    /// Amount of finished measured runs
+    pub runs: usize,
+    /// Amount of unmeasured runs, done before the measured ones
+    pub warmup: usize,
+    /// Seconds from the start of the first measured run until the end of the
+    /// last one. On timeout or error, the seconds since the host started
+    pub total_duration: f64,
+    /// Average duration in seconds
+    pub mean: Option<f64>,
+    /// Standard deviation of the durations
+    pub deviation: Option<f64>,
+    /// Shortest duration
+    pub min: Option<f64>,
+    /// Longest duration
+    pub max: Option<f64>,
+    /// Median duration
+    pub median: Option<f64>,
+    /// 90th percentile of the durations (linearly interpolated)
+    pub p90: Option<f64>,
+    /// 99th percentile of the durations (linearly interpolated)
+    pub p99: Option<f64>,
+    /// Coefficient of variation (deviation / mean)
+    pub cv: Option<f64>,
+    /// Bootstrapped confidence interval of the mean
+    pub confidence_interval: Option<ConfidenceInterval>,
+    /// Amount of durations outside of Tukey's fences (1.5 IQR beyond the
+    /// quartiles)
+    pub outliers: Option<usize>,
+    /// Whether `cv` is at most the stability threshold
+    pub stable: Option<bool>,
+    /// Every finished measured run, in order
+    pub samples: Vec<Sample>,
+    /// Time spent in each stage of the operation, by stage name
+    pub phases: BTreeMap<String, PhaseMetrics>,
+    /// Maximum memory used during the operation in Bytes. Peak resident set
+    /// size of the host across all runs, or as measured by runexec when
+    /// running a `PROGRAM`
+    pub memory: Option<u64>,
+    /// Average user CPU time per run in seconds
+    pub user_time: Option<f64>,
+    /// Average system CPU time per run in seconds
+    pub system_time: Option<f64>,
+    /// Size in Bytes of the serialized proof, if one was generated
+    pub proof_size: Option<usize>,
+    /// Serialization format, which was measured for `proofSize`
+    pub proof_encoding: Option<String>,
+    /// Cycles (instructions, trace length or steps) of the guest program
+    pub cycles: Option<u64>,
+    /// Cycles spent on memory paging
+    pub paging_cycles: Option<u64>,
+    /// Amount of segments (shards) of the execution
+    pub segments: Option<u64>,
+    /// Input profile of the guest, `default` for its default input. null when
+    /// the input was read from files, generated or changed with `--input`
+    pub profile: Option<String>,
+    /// Every configuration knob of the host, by name
+    pub config: BTreeMap<String, ConfigValue>,
+}
+
+impl RunMetrics {
+    /// Metrics of an operation, which failed before its host could report
+    /// anything
+    pub fn failed(error: ErrorInfo, total_duration: f64) -> RunMetrics {
+        RunMetrics {
+            schema_version: SCHEMA_VERSION,
+            status: error.kind.status(),
+            error: Some(error),
+            time_started: None,
+            runs: 0,
+            warmup: 0,
+            total_duration,
+            mean: None,
+            deviation: None,
+            min: None,
+            max: None,
+            median: None,
+            p90: None,
+            p99: None,
+            cv: None,
+            confidence_interval: None,
+            outliers: None,
+            stable: None,
+            samples: Vec::new(),
+            phases: BTreeMap::new(),
+            memory: None,
+            user_time: None,
+            system_time: None,
+            proof_size: None,
+            proof_encoding: None,
+            cycles: None,
+            paging_cycles: None,
+            segments: None,
+            profile: None,
+            config: BTreeMap::new(),
+        }
+    }
+
+    /// Metrics of an operation, which wasn't run because the host doesn't
+    /// support it
+    pub fn unsupported(message: String) -> RunMetrics {
+        let error = ErrorInfo {
+            kind: ErrorKind::Unsupported,
+            message,
+        };
+        RunMetrics::failed(error, 0.0)
+    }
+}
+
+/// Where the value of a configuration knob was taken from
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigSource {
+    /// `--set KEY=VALUE`
+    Cli,
+    Environment,
+    /// The guest's `default.env`
+    DefaultEnv,
+    /// The knob's default
+    Default,
+}
+
+/// Resolved value of a configuration knob
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ConfigValue {
+    pub value: String,
+    pub source: ConfigSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ConfidenceInterval {
+    pub level: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Sample {
+    /// Timestamp of the start of the run
+    pub time_started: String,
+    /// Duration of the run in seconds
+    pub duration: f64,
+    /// Peak resident set size of the host during the run in Bytes
+    pub peak_memory: Option<u64>,
+    /// User CPU time of the run in seconds
+    pub user_time: f64,
+    /// System CPU time of the run in seconds
+    pub system_time: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseMetrics {
+    /// Seconds spent once, outside of the measured runs
+    pub one_time: f64,
+    /// Seconds spent across all measured runs
+    pub total: f64,
+    /// Average seconds spent per measured run
+    pub mean: f64,
+}
+
+/// Metrics of all zkVMs for a single guest program, emitted by a `PROGRAM`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramMetrics {
+    pub schema_version: u32,
+    /// One object for every zkVM, which was benchmarked
+    pub benchmarking: Vec<Benchmark>,
+    /// Native execution, or null when it failed or was ignored
+    pub native: Option<RunMetrics>,
+    pub hardware: Hardware,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Benchmark {
+    /// Name of the zkVM
+    pub zkvm_name: String,
+    /// Commit or tag on which the zkVM is built
+    pub zkvm_rev: String,
+    /// Name of the benchmarked guest program
+    pub program_name: String,
+    /// Commit of the zkVMs-benchmarks repo
+    pub commit: String,
+    /// null when the host couldn't be started
+    pub execute: Option<RunMetrics>,
+    /// null when the host couldn't be started
+    pub prove: Option<RunMetrics>,
+    /// null when the host couldn't be started
+    pub verify: Option<RunMetrics>,
+    /// Mean duration of each operation, as a multiple of the native mean
+    /// duration
+    pub native_overhead: NativeOverhead,
+}
+
+impl Benchmark {
+    /// Metrics of the operation with the given name
+    pub fn operation_mut(&mut self, operation: &str) -> &mut Option<RunMetrics> {
+        match operation {
+            "execute" => &mut self.execute,
+            "prove" => &mut self.prove,
+            "verify" => &mut self.verify,
+            _ => panic!("Unknown operation \"{operation}\"!"),
+        }
+    }
+}
+
+/// Every field is null when either the operation or native execution is
+/// missing
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct NativeOverhead {
+    pub execute: Option<f64>,
+    pub prove: Option<f64>,
+    pub verify: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Hardware {
+    pub cpu: Vec<Cpu>,
+    pub memory: Memory,
+    pub hardware_acceleration: Vec<String>,
+    pub accelerated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Cpu {
+    pub model: String,
+    /// Physical core count
+    pub cores: u64,
+    /// Frequency in MHz
+    pub speed: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Memory {
+    /// Part number, only available with root permissions
+    pub model: Option<String>,
+    /// Total memory in Bytes
+    pub size: u64,
+    /// Speed in MT/s, only available with root permissions
+    pub speed: Option<u64>,
+}
+
+/// Metrics of a `--sweep`, emitted instead of the metrics of a single run
+/// (`T`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepMetrics<T> {
+    pub schema_version: u32,
+    /// Name of the swept input field
+    pub field: String,
+    /// One point for every swept value, in order
+    pub points: Vec<SweepPoint<T>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SweepPoint<T> {
+    /// Value of the swept field (or its length, for arrays and strings)
+    pub value: i64,
+    pub metrics: T,
+}
+
+/// What a zkVM host supports, printed with `--capabilities`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HostCapabilities {
+    /// Name of the zkVM
+    pub zkvm_name: String,
+    /// Commit or tag on which the zkVM is built
+    pub zkvm_rev: String,
+    /// Operations (run types) the host can run
+    pub operations: Vec<String>,
+    /// Kinds of proofs the host can generate
+    pub proof_modes: Vec<String>,
+    /// Operations for which the host reports cycle counts
+    pub cycle_counting: Vec<String>,
+    /// Operations which check the value, returned by the guest, against the
+    /// expected output
+    pub output_checking: Vec<String>,
+    /// Whether the generated proof can be written to a file with
+    /// `--proof-out`
+    pub proof_export: bool,
+    /// Whether a proof can be verified from a file with `--proof-in`
+    pub proof_import: bool,
+}
+
+impl HostCapabilities {
+    /// Runs the host at the given path with `--capabilities`
+    pub fn query(host: &str) -> Result<HostCapabilities, String> {
+        let output = std::process::Command::new(host)
+            .arg("--capabilities")
+            .output()
+            .map_err(|e| format!("Failed to run host \"{host}\": {e}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to query capabilities of host \"{host}\": {}",
+                output.status
+            ));
+        }
+        serde_json::from_slice(&output.stdout)
+            .map_err(|e| format!("Failed to parse capabilities of host \"{host}\": {e}"))
+    }
+
+    pub fn supports(&self, operation: &str) -> bool {
+        self.operations.iter().any(|o| o == operation)
+    }
+}
+
+/// Pretty printed JSON Schema of the given metrics type
+pub fn schema<T: JsonSchema>() -> String {
+    serde_json::to_string_pretty(&schema_for!(T)).expect("failed to serialize schema")
+}
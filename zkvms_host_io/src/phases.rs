@@ -0,0 +1,138 @@
+//! Timing of the individual stages (phases) inside a benchmarked operation.
+//!
+//! Hosts wrap each stage with `time_phase` (or hold a `PhaseTimer` for code,
+//! which cannot be put inside a closure, like `.await`s). Phases which happen
+//! outside of `benchmarkable!` are reported as one-time costs, while those
+//! inside of it are accumulated over all measured runs. Phases during warm-up
+//! runs are discarded.
+
+use crate::metrics::PhaseMetrics;
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    /// Key generation, preprocessing and loading of the guest program
+    Setup,
+    /// Execution of the guest without proving
+    Execute,
+    /// Proof generation
+    Prove,
+    /// Recursive compression or wrapping of the proof
+    Compress,
+    /// Proof verification
+    Verify,
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Setup => "setup",
+            Phase::Execute => "execute",
+            Phase::Prove => "prove",
+            Phase::Compress => "compress",
+            Phase::Verify => "verify",
+        }
+    }
+}
+
+/// Where newly timed phases go, set by the "benchmarkable" macro
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Recording {
+    OneTime,
+    Warmup,
+    Measured,
+}
+
+struct PhaseLog {
+    recording: Recording,
+    one_time: Vec<(Phase, Duration)>,
+    measured: Vec<(Phase, Duration)>,
+}
+
+static PHASES: Mutex<PhaseLog> = Mutex::new(PhaseLog {
+    recording: Recording::OneTime,
+    one_time: Vec::new(),
+    measured: Vec::new(),
+});
+
+/// Measures the time from its creation until it's dropped
+pub struct PhaseTimer {
+    phase: Phase,
+    start: Instant,
+}
+
+impl PhaseTimer {
+    pub fn start(phase: Phase) -> PhaseTimer {
+        PhaseTimer {
+            phase,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for PhaseTimer {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed();
+        let mut log = PHASES.lock().unwrap();
+        match log.recording {
+            Recording::OneTime => log.one_time.push((self.phase, duration)),
+            Recording::Warmup => {}
+            Recording::Measured => log.measured.push((self.phase, duration)),
+        }
+    }
+}
+
+/// Runs the given function, timing it as the given phase
+pub fn time_phase<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    let _timer = PhaseTimer::start(phase);
+    f()
+}
+
+/// Used by the "benchmarkable" macro
+pub fn record_phases(recording: Recording) {
+    PHASES.lock().unwrap().recording = recording;
+}
+
+/// Returns the metrics of every timed phase, by name, and clears them.
+///
+/// Every phase has a one-time duration, as well as a total and mean duration
+/// over all measured runs.
+pub(crate) fn take_phases(runs: usize) -> BTreeMap<String, PhaseMetrics> {
+    let mut log = PHASES.lock().unwrap();
+    let one_time = std::mem::take(&mut log.one_time);
+    let measured = std::mem::take(&mut log.measured);
+
+    let mut phases = one_time
+        .iter()
+        .chain(measured.iter())
+        .map(|(phase, _)| *phase)
+        .collect::<Vec<Phase>>();
+    phases.sort();
+    phases.dedup();
+
+    let sum = |log: &Vec<(Phase, Duration)>, phase: Phase| {
+        log.iter()
+            .filter(|(p, _)| *p == phase)
+            .map(|(_, d)| d.as_secs_f64())
+            // Summing starts at -0.0, which would show up for missing phases
+            .fold(0.0, |total, d| total + d)
+    };
+
+    let mut out = BTreeMap::new();
+    for phase in phases {
+        let total = sum(&measured, phase);
+        out.insert(
+            phase.name().to_string(),
+            PhaseMetrics {
+                one_time: sum(&one_time, phase),
+                total,
+                mean: total / runs as f64,
+            },
+        );
+    }
+    out
+}
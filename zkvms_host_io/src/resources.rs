@@ -0,0 +1,80 @@
+//! Measurement of the memory and CPU time, used by the host process itself.
+//!
+//! This way hosts report resource usage even without runexec (BenchExec).
+//! Peak memory is taken from `/proc/self/status` and is reset before every
+//! iteration, while CPU times come from `getrusage`. Both include the
+//! (terminated) children, which do all the work of some hosts.
+
+use std::{
+    fs::{read_to_string, write},
+    mem::MaybeUninit,
+};
+
+/// User and system CPU time in seconds, of this process and its
+/// (terminated) children
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct CpuTimes {
+    pub user: f64,
+    pub system: f64,
+}
+
+impl CpuTimes {
+    pub fn now() -> CpuTimes {
+        let (self_user, self_system) = rusage(libc::RUSAGE_SELF);
+        let (children_user, children_system) = rusage(libc::RUSAGE_CHILDREN);
+        CpuTimes {
+            user: self_user + children_user,
+            system: self_system + children_system,
+        }
+    }
+
+    /// CPU time spent since `earlier`
+    pub fn since(&self, earlier: &CpuTimes) -> CpuTimes {
+        CpuTimes {
+            user: self.user - earlier.user,
+            system: self.system - earlier.system,
+        }
+    }
+}
+
+fn getrusage(who: libc::c_int) -> libc::rusage {
+    let mut usage = MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: getrusage only writes into the given struct
+    if unsafe { libc::getrusage(who, usage.as_mut_ptr()) } != 0 {
+        panic!("getrusage failed: {}", std::io::Error::last_os_error());
+    }
+    // SAFETY: getrusage succeeded, so the struct is initialized
+    unsafe { usage.assume_init() }
+}
+
+/// User and system CPU time in seconds
+fn rusage(who: libc::c_int) -> (f64, f64) {
+    let usage = getrusage(who);
+    let seconds = |t: libc::timeval| t.tv_sec as f64 + t.tv_usec as f64 / 1_000_000.0;
+    (seconds(usage.ru_utime), seconds(usage.ru_stime))
+}
+
+/// Resets the peak resident set size of this process, so `peak_memory`
+/// only reports the peak from now on. Returns whether it was reset, which
+/// kernels without `/proc/self/clear_refs` don't support.
+pub fn reset_peak_memory() -> bool {
+    write("/proc/self/clear_refs", "5").is_ok()
+}
+
+/// Peak resident set size in Bytes, of this process since the last
+/// `reset_peak_memory` or of its largest (terminated) child, whichever is
+/// bigger. The children's peak can't be reset, so it is over the whole
+/// lifetime of the host.
+pub fn peak_memory() -> Option<u64> {
+    let status = read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    let children_kilobytes = getrusage(libc::RUSAGE_CHILDREN).ru_maxrss as u64;
+    Some(kilobytes.max(children_kilobytes) * 1024)
+}
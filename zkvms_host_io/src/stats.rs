@@ -0,0 +1,174 @@
+//! Summary statistics over the durations of benchmark runs.
+
+/// Amount of resamples, used for the bootstrap confidence interval
+static BOOTSTRAP_RESAMPLES: usize = 1000;
+/// Confidence level of the bootstrap confidence interval
+pub static CONFIDENCE_LEVEL: f64 = 0.95;
+/// Fixed seed, so the same durations always produce the same interval
+static BOOTSTRAP_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub mean: f64,
+    pub deviation: f64,
+    pub min: f64,
+    pub max: f64,
+    pub median: f64,
+    pub p90: f64,
+    pub p99: f64,
+    /// Coefficient of variation (deviation / mean)
+    pub cv: f64,
+    /// Bootstrap confidence interval of the mean
+    pub ci_low: f64,
+    pub ci_high: f64,
+    /// Amount of durations outside of Tukey's fences
+    pub outliers: usize,
+}
+
+impl Summary {
+    /// Computes all statistics over a non-empty list of durations
+    pub fn new(xs: &[f64]) -> Summary {
+        let mut sorted = xs.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let mean = mean(xs);
+        let deviation = stddev(xs);
+        let (ci_low, ci_high) = bootstrap_ci(xs);
+
+        // Tukey's fences
+        let q1 = percentile(&sorted, 25.0);
+        let q3 = percentile(&sorted, 75.0);
+        let iqr = q3 - q1;
+        let outliers = xs
+            .iter()
+            .filter(|&&x| x < q1 - 1.5 * iqr || x > q3 + 1.5 * iqr)
+            .count();
+
+        Summary {
+            mean,
+            deviation,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            median: percentile(&sorted, 50.0),
+            p90: percentile(&sorted, 90.0),
+            p99: percentile(&sorted, 99.0),
+            cv: if mean == 0.0 { 0.0 } else { deviation / mean },
+            ci_low,
+            ci_high,
+            outliers,
+        }
+    }
+}
+
+fn mean(xs: &[f64]) -> f64 {
+    if xs.len() == 1 {
+        xs[0]
+    } else {
+        statistical::mean(xs)
+    }
+}
+
+fn stddev(xs: &[f64]) -> f64 {
+    if xs.len() == 1 {
+        0.0
+    } else {
+        statistical::standard_deviation(xs, None)
+    }
+}
+
+/// Linearly interpolated percentile of sorted values
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Percentile bootstrap confidence interval of the mean
+fn bootstrap_ci(xs: &[f64]) -> (f64, f64) {
+    let mut rng = XorShift(BOOTSTRAP_SEED);
+    let mut means = (0..BOOTSTRAP_RESAMPLES)
+        .map(|_| {
+            let resample = (0..xs.len())
+                .map(|_| xs[rng.next() as usize % xs.len()])
+                .collect::<Vec<f64>>();
+            mean(&resample)
+        })
+        .collect::<Vec<f64>>();
+    means.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let alpha = (1.0 - CONFIDENCE_LEVEL) / 2.0 * 100.0;
+    (percentile(&means, alpha), percentile(&means, 100.0 - alpha))
+}
+
+/// Minimal xorshift64 generator, good enough for resampling
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{actual} is not {expected}"
+        );
+    }
+
+    #[test]
+    fn percentiles_are_interpolated() {
+        let sorted = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_close(percentile(&sorted, 0.0), 1.0);
+        assert_close(percentile(&sorted, 25.0), 2.0);
+        assert_close(percentile(&sorted, 50.0), 3.0);
+        assert_close(percentile(&sorted, 90.0), 4.6);
+        assert_close(percentile(&sorted, 99.0), 4.96);
+        assert_close(percentile(&sorted, 100.0), 5.0);
+    }
+
+    #[test]
+    fn summary_of_known_durations() {
+        let summary = Summary::new(&[3.0, 1.0, 5.0, 2.0, 4.0]);
+        assert_close(summary.mean, 3.0);
+        assert_close(summary.deviation, 2.5f64.sqrt());
+        assert_close(summary.min, 1.0);
+        assert_close(summary.max, 5.0);
+        assert_close(summary.median, 3.0);
+        assert_close(summary.p90, 4.6);
+        assert_close(summary.cv, 2.5f64.sqrt() / 3.0);
+        assert_eq!(summary.outliers, 0);
+    }
+
+    #[test]
+    fn bootstrap_ci_with_fixed_seed() {
+        let (low, high) = bootstrap_ci(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_close(low, 1.8);
+        assert_close(high, 4.2);
+        assert_eq!(bootstrap_ci(&[1.0, 2.0, 3.0, 4.0, 5.0]), (low, high));
+    }
+
+    #[test]
+    fn single_duration() {
+        let summary = Summary::new(&[2.0]);
+        assert_close(summary.mean, 2.0);
+        assert_close(summary.deviation, 0.0);
+        assert_close(summary.cv, 0.0);
+        assert_close(summary.ci_low, 2.0);
+        assert_close(summary.ci_high, 2.0);
+    }
+
+    #[test]
+    fn outliers_outside_tukey_fences() {
+        // Q1 = 2 and Q3 = 4, so the fences are at -1 and 7
+        assert_eq!(Summary::new(&[1.0, 2.0, 3.0, 4.0, 100.0]).outliers, 1);
+        assert_eq!(Summary::new(&[-5.0, 2.0, 3.0, 4.0, 7.0]).outliers, 1);
+    }
+}
@@ -0,0 +1,161 @@
+//! Sweeps over the values of an input field and sizes of generated inputs,
+//! for scaling curves.
+//!
+//! This file is also included by zkvms_guest_io, so the zkVM hosts and the
+//! orchestrator accept the same sweep specifications.
+
+/// Values, which a single input field takes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sweep {
+    pub field: String,
+    pub values: Vec<i64>,
+}
+
+impl Sweep {
+    /// Parses `FIELD=START..END:STEP` (END is inclusive and STEP defaults to
+    /// 1) or `FIELD=VALUE,VALUE,...`
+    pub fn parse(spec: &str) -> Result<Sweep, String> {
+        let (field, values) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("expected FIELD=START..END:STEP, found \"{spec}\""))?;
+        if field.is_empty() {
+            return Err(format!("missing field name in \"{spec}\""));
+        }
+
+        let number = |x: &str| {
+            x.trim()
+                .parse::<i64>()
+                .map_err(|_| format!("\"{x}\" isn't a whole number"))
+        };
+
+        let values = match values.split_once("..") {
+            Some((start, rest)) => {
+                let (end, step) = rest.split_once(':').unwrap_or((rest, "1"));
+                let (start, end, step) = (number(start)?, number(end)?, number(step)?);
+                if step <= 0 {
+                    return Err(format!("the step of \"{spec}\" must be positive"));
+                }
+                if start > end {
+                    return Err(format!("the start of \"{spec}\" is after its end"));
+                }
+                (start..=end).step_by(step as usize).collect()
+            }
+            None => values.split(',').map(number).collect::<Result<_, _>>()?,
+        };
+
+        Ok(Sweep {
+            field: field.to_string(),
+            values,
+        })
+    }
+
+    /// `FIELD=VALUE` argument of `--sweep-point`, for every swept value
+    pub fn points(&self) -> Vec<String> {
+        self.values
+            .iter()
+            .map(|value| format!("{}={value}", self.field))
+            .collect()
+    }
+}
+
+/// Parses the `FIELD=VALUE` argument of `--sweep-point`
+pub fn parse_point(point: &str) -> Result<(String, i64), String> {
+    match Sweep::parse(point)? {
+        Sweep { field, values } if values.len() == 1 => Ok((field, values[0])),
+        _ => Err(format!("expected FIELD=VALUE, found \"{point}\"")),
+    }
+}
+
+/// Parses the `size=N` argument of `--generate`
+pub fn parse_generate(argument: &str) -> Result<usize, String> {
+    match argument.split_once('=') {
+        Some(("size", size)) => size
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("\"{size}\" isn't a valid size")),
+        _ => Err(format!("expected size=N, found \"{argument}\"")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(spec: &str) -> Vec<i64> {
+        Sweep::parse(spec).unwrap().values
+    }
+
+    #[test]
+    fn ranges_include_their_end() {
+        assert_eq!(values("n=1..4"), [1, 2, 3, 4]);
+        assert_eq!(values("n=0..10:5"), [0, 5, 10]);
+        assert_eq!(values("n=0..9:5"), [0, 5]);
+        assert_eq!(values("n=-2..-1"), [-2, -1]);
+        assert_eq!(values("n=3..3"), [3]);
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(
+            Sweep::parse("size=8, 2,4").unwrap(),
+            Sweep {
+                field: "size".to_string(),
+                values: vec![8, 2, 4],
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_sweeps() {
+        assert_eq!(
+            Sweep::parse("n").unwrap_err(),
+            "expected FIELD=START..END:STEP, found \"n\""
+        );
+        assert_eq!(
+            Sweep::parse("=1..2").unwrap_err(),
+            "missing field name in \"=1..2\""
+        );
+        assert_eq!(
+            Sweep::parse("n=1..x").unwrap_err(),
+            "\"x\" isn't a whole number"
+        );
+        assert_eq!(
+            Sweep::parse("n=1..4:0").unwrap_err(),
+            "the step of \"n=1..4:0\" must be positive"
+        );
+        assert_eq!(
+            Sweep::parse("n=4..1").unwrap_err(),
+            "the start of \"n=4..1\" is after its end"
+        );
+        assert_eq!(
+            Sweep::parse("n=1,,2").unwrap_err(),
+            "\"\" isn't a whole number"
+        );
+    }
+
+    #[test]
+    fn points() {
+        assert_eq!(
+            Sweep::parse("n=1..3").unwrap().points(),
+            ["n=1", "n=2", "n=3"]
+        );
+        assert_eq!(parse_point("n=5"), Ok(("n".to_string(), 5)));
+        assert_eq!(
+            parse_point("n=1,2"),
+            Err("expected FIELD=VALUE, found \"n=1,2\"".to_string())
+        );
+    }
+
+    #[test]
+    fn generate() {
+        assert_eq!(parse_generate("size=16"), Ok(16));
+        assert_eq!(
+            parse_generate("size=-1"),
+            Err("\"-1\" isn't a valid size".to_string())
+        );
+        assert_eq!(
+            parse_generate("length=16"),
+            Err("expected size=N, found \"length=16\"".to_string())
+        );
+    }
+}